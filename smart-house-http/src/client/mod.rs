@@ -144,4 +144,18 @@ impl SmartHouseClient {
 
         Ok(report)
     }
+
+    /// The report rendered in `format`, like "markdown" or "csv"
+    pub async fn get_rendered_report(
+        &self,
+        house_id: i32,
+        format: &str,
+    ) -> SmartHouseClientResult<String> {
+        let url = format!("{}/houses/{}/report?format={}", &self.url, house_id, format);
+
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        let report = response.text().await?;
+
+        Ok(report)
+    }
 }
@@ -8,6 +8,7 @@ use diesel_derive_enum::DbEnum;
 use rocket::http::Status;
 use rocket_contrib::json::Json;
 use serde::{Deserialize, Serialize};
+use smart_house::report::model::DeviceEntry;
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, DbEnum)]
 #[PgType = "device_item"]
@@ -60,6 +61,28 @@ impl Device {
             }
         }
     }
+
+    /// The device in a structured report, described by its status
+    pub fn get_entry(&self, room_name: String) -> DeviceEntry {
+        let values = match self.type_ {
+            DeviceItem::Thermometer => {
+                let thermo: Thermometer = serde_json::from_str(&self.data).unwrap();
+
+                vec![("temperature".to_string(), thermo.temperature.to_string())]
+            }
+            DeviceItem::Socket => {
+                let socket: Socket = serde_json::from_str(&self.data).unwrap();
+                let state = match socket.status {
+                    true => "On",
+                    false => "Off",
+                };
+
+                vec![("state".to_string(), state.to_string())]
+            }
+        };
+
+        DeviceEntry::new(&self.name, self.get_status(room_name)).with_values(values)
+    }
 }
 
 #[get("/<fid>")]
@@ -7,9 +7,12 @@ use diesel::associations::HasTable;
 use diesel::ExpressionMethods;
 use diesel::QueryDsl;
 use diesel::QueryResult;
-use rocket::http::Status;
+use rocket::http::{ContentType, Status};
+use rocket::response::content::Content;
 use rocket_contrib::json::Json;
 use serde::{Deserialize, Serialize};
+use smart_house::report::model::{Report, RoomReport};
+use smart_house::report::render::ReportFormat;
 
 #[derive(Serialize, Deserialize, Identifiable, Queryable, Debug, Insertable, PartialEq, Eq)]
 #[table_name = "houses"]
@@ -135,14 +138,25 @@ pub struct HouseReport {
     pub report: String,
 }
 
-#[get("/<fid>/report")]
-pub fn get_report(fid: i32, conn: db_pool::DbConn) -> Result<Json<HouseReport>, Status> {
+/// Without `format` the device statuses wrapped in a `HouseReport`, otherwise
+/// the report rendered as text, markdown, json, csv or html
+#[get("/<fid>/report?<format>")]
+pub fn get_report(
+    fid: i32,
+    format: Option<String>,
+    conn: db_pool::DbConn,
+) -> Result<Content<String>, Status> {
     use super::schema::devices;
     use super::schema::house_rooms::dsl::*;
     use super::schema::houses;
     use super::schema::houses::columns::id as houses_id;
     use super::schema::rooms;
 
+    let format = format
+        .map(|format| format.parse::<ReportFormat>())
+        .transpose()
+        .map_err(|_| Status::BadRequest)?;
+
     let house = houses::table
         .find(fid)
         .first::<House>(&*conn)
@@ -155,15 +169,50 @@ pub fn get_report(fid: i32, conn: db_pool::DbConn) -> Result<Json<HouseReport>,
         .load::<(String, Device)>(&*conn)
         .expect("Failed to find house devices");
 
-    let report_data: Vec<String> = house_devices
-        .into_iter()
-        .map(|(room_name, device)| device.get_status(room_name))
-        .collect();
+    let format = match format {
+        Some(format) => format,
+        None => {
+            let report_data: Vec<String> = house_devices
+                .into_iter()
+                .map(|(room_name, device)| device.get_status(room_name))
+                .collect();
+
+            let report = HouseReport {
+                house: house.name,
+                report: report_data.join("\n"),
+            };
+            let body = serde_json::to_string(&report).expect("Report has only string keys");
+
+            return Ok(Content(ContentType::JSON, body));
+        }
+    };
 
-    let report = HouseReport {
+    let mut report = Report {
         house: house.name,
-        report: report_data.join("\n"),
+        rooms: Vec::new(),
     };
 
-    Ok(Json(report))
+    for (room_name, device) in house_devices {
+        let entry = device.get_entry(room_name.clone());
+
+        match report.rooms.iter_mut().find(|room| room.name == room_name) {
+            Some(room) => room.devices.push(entry),
+            None => report.rooms.push(RoomReport {
+                name: room_name,
+                devices: vec![entry],
+            }),
+        }
+    }
+
+    Ok(Content(content_type(format), report.render(format)))
+}
+
+fn content_type(format: ReportFormat) -> ContentType {
+    match format {
+        ReportFormat::Text => ContentType::Plain,
+        ReportFormat::Markdown => ContentType::new("text", "markdown"),
+        ReportFormat::Json => ContentType::JSON,
+        ReportFormat::Csv => ContentType::CSV,
+        ReportFormat::Html => ContentType::HTML,
+    }
 }
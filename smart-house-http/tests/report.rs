@@ -1,5 +1,6 @@
 use diesel::RunQueryDsl;
 use dotenv::dotenv;
+use rocket::http::Status;
 use rocket::local::Client;
 use rocket::routes;
 use smart_house_http::db_pool;
@@ -94,5 +95,20 @@ async fn get_report() -> Result<(), Box<dyn std::error::Error>> {
 
     assert_eq!(house_report.report, expected_house_report);
 
+    let path = format!("/houses/{}/report?format=csv", house.id);
+    let mut response = client.get(&path).dispatch();
+
+    let expected_csv = format!(
+        "room,device,status,details\n{},generated_device#1,ok,temperature: 7",
+        new_room_id
+    );
+
+    assert_eq!(response.body_string().unwrap(), expected_csv);
+
+    let path = format!("/houses/{}/report?format=pdf", house.id);
+    let response = client.get(&path).dispatch();
+
+    assert_eq!(response.status(), Status::BadRequest);
+
     Ok(())
 }
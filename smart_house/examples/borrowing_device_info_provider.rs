@@ -33,19 +33,16 @@ impl<'a, 'b> DeviceInfoProvider for BorrowingDeviceInfoProvider<'a, 'b> {
 fn main() {
     let mut house = House::new("My home [borrowing]");
 
-    let room1 = Room {
-        name: "Kitchen".to_string(),
-        device_names: vec![
+    let room1 = Room::new(
+        "Kitchen",
+        vec![
             "my socket".to_string(),
             "thermometer".to_string(),
             "non-existent device".to_string(),
         ],
-    };
+    );
 
-    let room2 = Room {
-        name: "Hallway".to_string(),
-        device_names: vec!["door sensor".to_string()],
-    };
+    let room2 = Room::new("Hallway", vec!["door sensor".to_string()]);
 
     let _r1 = house.add_room(room1);
     let _r2 = house.add_room(room2);
@@ -1,16 +1,13 @@
 use smart_house::room::Room;
 
 fn main() {
-    let mut room = Room {
-        name: "Kitchen".to_string(),
-        device_names: vec![],
-    };
+    let mut room = Room::new("Kitchen", vec![]);
 
     println!("--- Adding devices ---");
     let _r1 = room.add_device("my socket".to_string());
     let _r2 = room.add_devices(vec!["thermometer".to_string(), "tv".to_string()]);
 
-    for device in &room.device_names {
+    for device in room.device_names() {
         println!("{:?}", device);
     }
 
@@ -22,9 +19,9 @@ fn main() {
 
     println!();
     println!("--- Removing the device ---");
-    room.remove_device("tv");
+    let _r3 = room.remove_device("tv");
 
-    for device in &room.device_names {
+    for device in room.device_names() {
         println!("{:?}", device);
     }
 
@@ -0,0 +1,28 @@
+use smart_house::{
+    devices::{socket::Socket, thermometer::Thermometer},
+    house::House,
+    room::Room,
+};
+
+fn main() {
+    let mut house = House::new("My home [house devices]");
+
+    let mut kitchen = Room::new("Kitchen", Vec::new());
+    let _r1 = kitchen.add_device_item(Socket::new("my socket", "Kitchen", false, 0.0));
+    let _r2 = kitchen.add_device_item(Thermometer::new("thermometer", "Kitchen", true, 24.0));
+    let _r3 = kitchen.add_device("non-existent device".to_string());
+
+    let _r4 = house.add_room(kitchen);
+
+    println!("--- Report ---");
+    println!("{}", house.report());
+
+    println!();
+    println!("--- Turning the socket on ---");
+    if let Some(socket) = house.get_device_mut::<Socket>("Kitchen", "my socket") {
+        socket.status = true;
+        socket.power_consumption = 4.0;
+    }
+
+    println!("{}", house.report());
+}
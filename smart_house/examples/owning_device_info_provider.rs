@@ -26,10 +26,10 @@ impl DeviceInfoProvider for OwningDeviceInfoProvider {
 fn main() {
     let mut house = House::new("My home [owning]");
 
-    let room = Room {
-        name: "Living room".to_string(),
-        device_names: vec!["my socket".to_string(), "non-existent device".to_string()],
-    };
+    let room = Room::new(
+        "Living room",
+        vec!["my socket".to_string(), "non-existent device".to_string()],
+    );
 
     let _r1 = house.add_room(room);
 
@@ -3,24 +3,18 @@ use smart_house::{house::House, room::Room};
 fn main() {
     let mut house = House::new("My home [borrowing]");
 
-    let room1 = Room {
-        name: "Kitchen".to_string(),
-        device_names: vec![
+    let room1 = Room::new(
+        "Kitchen",
+        vec![
             "my socket".to_string(),
             "thermometer".to_string(),
             "non-existent device".to_string(),
         ],
-    };
+    );
 
-    let room2 = Room {
-        name: "Hallway".to_string(),
-        device_names: vec!["door sensor".to_string()],
-    };
+    let room2 = Room::new("Hallway", vec!["door sensor".to_string()]);
 
-    let room3 = Room {
-        name: "Living room".to_string(),
-        device_names: vec!["tv".to_string(), "thermo".to_string()],
-    };
+    let room3 = Room::new("Living room", vec!["tv".to_string(), "thermo".to_string()]);
 
     let rooms = vec![room1, room2];
 
@@ -34,16 +28,27 @@ fn main() {
 
     println!();
     println!("--- Trying to add an existing room ---");
-    let result = house.add_room(Room {
-        name: "Kitchen".to_string(),
-        device_names: Vec::new(),
-    });
+    let result = house.add_room(Room::new("Kitchen", Vec::new()));
 
     println!("{:?}", result);
 
     println!();
     println!("--- Removing the room ---");
-    house.remove_room("Kitchen");
+    let _r3 = house.remove_room("Kitchen");
+
+    for room in house.get_rooms() {
+        println!("{:?}", room);
+    }
+
+    println!();
+    println!("--- Trying to remove a removed room ---");
+    let result = house.remove_room("Kitchen");
+
+    println!("{:?}", result);
+
+    println!();
+    println!("--- Moving the device ---");
+    let _r4 = house.move_device("Living room", "Hallway", "tv");
 
     for room in house.get_rooms() {
         println!("{:?}", room);
@@ -0,0 +1,423 @@
+use std::fs;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::{
+    devices::device::{Device, DeviceItem},
+    group::RoomGroup,
+    house::House,
+    room::Room,
+};
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    #[error("Cannot access house configuration: {}", .0)]
+    IoError(String),
+    #[error("Unknown house configuration format {:?}", .0)]
+    UnknownFormat(String),
+    #[error("Cannot parse house configuration: {}", .0)]
+    ParseError(String),
+    #[error("Cannot serialize house configuration: {}", .0)]
+    SerializeError(String),
+    #[error("Room {:?}: {}", .room, .reason)]
+    InvalidRoom { room: String, reason: String },
+    #[error("Floor {:?}: {}", .floor, .reason)]
+    InvalidFloor { floor: String, reason: String },
+    #[error("Zone {:?}: {}", .zone, .reason)]
+    InvalidZone { zone: String, reason: String },
+    #[error("Device {:?} in room {:?}: {}", .device, .room, .reason)]
+    InvalidDevice {
+        room: String,
+        device: String,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Format of the file by its extension
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+
+        match extension.to_lowercase().as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "toml" => Ok(ConfigFormat::Toml),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            _ => Err(ConfigError::UnknownFormat(extension.to_string())),
+        }
+    }
+}
+
+impl House {
+    /// Reads the house from a JSON, TOML or YAML file, picked by the extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)?;
+        let config = fs::read_to_string(path).map_err(|e| ConfigError::IoError(e.to_string()))?;
+
+        Self::from_config(&config, format)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let config = self.to_config(ConfigFormat::from_path(path)?)?;
+
+        fs::write(path, config).map_err(|e| ConfigError::IoError(e.to_string()))
+    }
+
+    /// Parses and validates the house. Devices may leave out their parent
+    /// room and rooms their device names, both are taken from the owned
+    /// devices.
+    pub fn from_config(config: &str, format: ConfigFormat) -> Result<Self, ConfigError> {
+        let house: House = match format {
+            ConfigFormat::Json => serde_json::from_str(config).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::from_str(config).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(config).map_err(|e| e.to_string()),
+        }
+        .map_err(ConfigError::ParseError)?;
+
+        house.validate()?;
+
+        Ok(house)
+    }
+
+    pub fn to_config(&self, format: ConfigFormat) -> Result<String, ConfigError> {
+        self.validate()?;
+
+        match format {
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            // Going through a value puts tables after plain values, as TOML requires
+            ConfigFormat::Toml => toml::Value::try_from(self)
+                .and_then(|value| toml::to_string_pretty(&value))
+                .map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+        }
+        .map_err(ConfigError::SerializeError)
+    }
+
+    /// Checks the names are unique, the devices belong to their rooms and
+    /// floors and zones are made of existing rooms
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (idx, room) in self.get_rooms().iter().enumerate() {
+            let invalid_room = |reason: &str| ConfigError::InvalidRoom {
+                room: room.name.to_string(),
+                reason: reason.to_string(),
+            };
+
+            if room.name.is_empty() {
+                return Err(invalid_room("name is empty"));
+            }
+
+            if self.get_rooms()[..idx].iter().any(|r| r.name == room.name) {
+                return Err(invalid_room("room is listed twice"));
+            }
+
+            validate_room(room)?;
+        }
+
+        for (idx, floor) in self.floors().iter().enumerate() {
+            let invalid_floor = |reason: &str| ConfigError::InvalidFloor {
+                floor: floor.name.to_string(),
+                reason: reason.to_string(),
+            };
+
+            let previous = &self.floors()[..idx];
+            if let Some(reason) = invalid_group(self, floor, previous) {
+                return Err(invalid_floor(&reason));
+            }
+
+            let on_other_floor = floor
+                .room_names
+                .iter()
+                .enumerate()
+                .find(|(idx, room_name)| {
+                    floor.room_names[..*idx].contains(room_name)
+                        || previous.iter().any(|f| f.contains(room_name))
+                });
+
+            if let Some((_, room_name)) = on_other_floor {
+                let reason = format!("room {:?} is on another floor", room_name);
+                return Err(invalid_floor(&reason));
+            }
+        }
+
+        for (idx, zone) in self.zones().iter().enumerate() {
+            if let Some(reason) = invalid_group(self, zone, &self.zones()[..idx]) {
+                return Err(ConfigError::InvalidZone {
+                    zone: zone.name.to_string(),
+                    reason,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_room(room: &Room) -> Result<(), ConfigError> {
+    let invalid_device = |device: &str, reason: &str| ConfigError::InvalidDevice {
+        room: room.name.to_string(),
+        device: device.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let device_names = room.device_names();
+
+    for (idx, device_name) in device_names.iter().enumerate() {
+        if device_name.is_empty() {
+            return Err(invalid_device(device_name, "name is empty"));
+        }
+
+        if device_names[..idx].contains(device_name) {
+            return Err(invalid_device(device_name, "device is listed twice"));
+        }
+    }
+
+    for device in room.devices() {
+        let device_name = device.get_name();
+
+        if device.parent_room() != room.name {
+            let reason = format!("device belongs to room {:?}", device.parent_room());
+            return Err(invalid_device(&device_name, &reason));
+        }
+
+        if let Some(reason) = invalid_value(device) {
+            return Err(invalid_device(&device_name, reason));
+        }
+    }
+
+    Ok(())
+}
+
+// Checks a floor or zone against the ones listed before it
+fn invalid_group(house: &House, group: &RoomGroup, previous: &[RoomGroup]) -> Option<String> {
+    if group.name.is_empty() {
+        return Some("name is empty".to_string());
+    }
+
+    if previous.iter().any(|g| g.name == group.name) {
+        return Some("name is listed twice".to_string());
+    }
+
+    group
+        .room_names
+        .iter()
+        .find(|room_name| !house.is_room_exist(room_name))
+        .map(|room_name| format!("cannot find room {:?}", room_name))
+}
+
+fn invalid_value(device: &DeviceItem) -> Option<&'static str> {
+    match device {
+        DeviceItem::Socket(socket)
+            if !socket.power_consumption.is_finite() || socket.power_consumption < 0.0 =>
+        {
+            Some("power consumption must be a non-negative number")
+        }
+        DeviceItem::Thermometer(thermometer) if !thermometer.temperature.kelvin().is_finite() => {
+            Some("temperature must be a number")
+        }
+        DeviceItem::Thermometer(thermometer) if thermometer.temperature.kelvin() < 0.0 => {
+            Some("temperature is below absolute zero")
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_config {
+    use thermometer_udp::metric::Metric;
+    use thermometer_udp::temperature::{Calibration, TemperatureUnit};
+
+    use crate::devices::{
+        environment_sensor::EnvironmentSensor, socket::Socket, thermometer::Thermometer,
+    };
+    use crate::house::HouseError;
+
+    use super::*;
+
+    fn house() -> Result<House, HouseError> {
+        let mut kitchen = Room::with_devices(
+            "Kitchen",
+            vec![
+                Socket::new("kettle", "Kitchen", true, 1500.0).into(),
+                Thermometer::new("thermometer", "Kitchen", true, 24.5)
+                    .with_unit(TemperatureUnit::Fahrenheit)
+                    .with_calibration(Calibration::new(-0.5, 1.0))
+                    .into(),
+                EnvironmentSensor::new("sensor", "Kitchen", true)
+                    .with_metric(Metric::Humidity, 40.0)
+                    .with_metric(Metric::Co2, 600.0)
+                    .into(),
+            ],
+        )?;
+        kitchen.add_device("tv".to_string())?;
+
+        let mut house = House::new("My house");
+        house.add_room(kitchen)?;
+        house.add_room(Room::new("Hallway", Vec::new()))?;
+        house.add_floor(RoomGroup::new(
+            "Ground floor",
+            vec!["Kitchen".to_string(), "Hallway".to_string()],
+        ))?;
+        house.add_zone(RoomGroup::new("Heating zone", vec!["Kitchen".to_string()]))?;
+
+        Ok(house)
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let house = house()?;
+
+        for format in [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let config = house.to_config(format)?;
+
+            assert_eq!(House::from_config(&config, format)?, house);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("house.yml")),
+            Ok(ConfigFormat::Yaml)
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("house.ini")),
+            Err(ConfigError::UnknownFormat("ini".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_fill_in_devices() -> Result<(), ConfigError> {
+        let config = r#"
+            name = "My house"
+
+            [[rooms]]
+            name = "Kitchen"
+
+            [[rooms.devices]]
+            type = "socket"
+            name = "kettle"
+            status = false
+            power_consumption = 0.0
+        "#;
+
+        let house = House::from_config(config, ConfigFormat::Toml)?;
+
+        assert_eq!(house.devices("Kitchen"), Ok(vec!["kettle".to_string()]));
+        assert_eq!(
+            house.device("Kitchen", "kettle").unwrap().parent_room(),
+            "Kitchen"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_room() {
+        let config = r#"{"name": "My house", "rooms": [{"name": "Kitchen"}, {"name": "Kitchen"}]}"#;
+
+        let error = House::from_config(config, ConfigFormat::Json).unwrap_err();
+
+        assert_eq!(error.to_string(), "Room \"Kitchen\": room is listed twice");
+    }
+
+    #[test]
+    fn test_invalid_device() {
+        let config = "
+name: My house
+rooms:
+  - name: Kitchen
+    devices:
+      - type: socket
+        name: kettle
+        parent_room: Hallway
+        status: true
+        power_consumption: 1500
+";
+
+        let error = House::from_config(config, ConfigFormat::Yaml).unwrap_err();
+
+        assert_eq!(
+            error,
+            ConfigError::InvalidDevice {
+                room: "Kitchen".to_string(),
+                device: "kettle".to_string(),
+                reason: "device belongs to room \"Hallway\"".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let config = r#"{"name": "My house", "rooms": [{"name": "Kitchen", "devices": [
+            {"type": "thermometer", "name": "thermometer", "status": true, "temperature": -300}
+        ]}]}"#;
+
+        let error = House::from_config(config, ConfigFormat::Json).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Device \"thermometer\" in room \"Kitchen\": temperature is below absolute zero"
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let result = House::from_config("name: [", ConfigFormat::Yaml);
+
+        assert!(matches!(result, Err(ConfigError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_invalid_floor() {
+        let config = r#"
+            name = "My house"
+
+            [[rooms]]
+            name = "Kitchen"
+
+            [[floors]]
+            name = "Ground floor"
+            room_names = ["Kitchen"]
+
+            [[floors]]
+            name = "First floor"
+            room_names = ["Kitchen"]
+        "#;
+
+        let error = House::from_config(config, ConfigFormat::Toml).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Floor \"First floor\": room \"Kitchen\" is on another floor"
+        );
+    }
+
+    #[test]
+    fn test_invalid_zone() {
+        let config = r#"{"name": "My house", "zones": [{"name": "North", "room_names": ["Attic"]}], "rooms": []}"#;
+
+        let error = House::from_config(config, ConfigFormat::Json).unwrap_err();
+
+        assert_eq!(
+            error,
+            ConfigError::InvalidZone {
+                zone: "North".to_string(),
+                reason: "cannot find room \"Attic\"".to_string(),
+            }
+        );
+    }
+}
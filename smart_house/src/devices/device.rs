@@ -1,6 +1,13 @@
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "network")]
+use socket_tcp::client::ConnectionError;
+#[cfg(feature = "network")]
+use thermometer_udp::thermometer::ThermometerError;
 use thiserror::Error;
 
-use super::{socket::Socket, thermometer::Thermometer};
+use super::{environment_sensor::EnvironmentSensor, socket::Socket, thermometer::Thermometer};
+use crate::report::model::DeviceEntry;
 
 #[derive(Debug, Error)]
 pub enum DeviceConnectionError {
@@ -8,21 +15,157 @@ pub enum DeviceConnectionError {
     NotFoundError(String),
     #[error("Connection timed out")]
     TimedOutError,
+    #[error("Cannot connect to device: {}", .0)]
+    ConnectionError(String),
 }
 
-#[derive(Debug, PartialEq)]
+#[cfg(feature = "network")]
+impl From<ThermometerError> for DeviceConnectionError {
+    fn from(e: ThermometerError) -> Self {
+        match e {
+            ThermometerError::Stale { .. } => DeviceConnectionError::TimedOutError,
+            e => DeviceConnectionError::ConnectionError(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "network")]
+impl From<ConnectionError> for DeviceConnectionError {
+    fn from(e: ConnectionError) -> Self {
+        DeviceConnectionError::ConnectionError(e.to_string())
+    }
+}
+
+/// Serialized with a `type` field naming the variant, like `"type": "socket"`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum DeviceItem {
     Thermometer(Thermometer),
     Socket(Socket),
+    EnvironmentSensor(EnvironmentSensor),
+}
+
+impl DeviceItem {
+    pub fn parent_room(&self) -> &str {
+        match self {
+            DeviceItem::Socket(socket) => &socket.parent_room,
+            DeviceItem::Thermometer(thermometer) => &thermometer.parent_room,
+            DeviceItem::EnvironmentSensor(sensor) => &sensor.parent_room,
+        }
+    }
+
+    pub fn set_parent_room(&mut self, room_name: &str) {
+        let parent_room = match self {
+            DeviceItem::Socket(socket) => &mut socket.parent_room,
+            DeviceItem::Thermometer(thermometer) => &mut thermometer.parent_room,
+            DeviceItem::EnvironmentSensor(sensor) => &mut sensor.parent_room,
+        };
+
+        *parent_room = room_name.to_string();
+    }
+}
+
+impl From<Socket> for DeviceItem {
+    fn from(socket: Socket) -> Self {
+        DeviceItem::Socket(socket)
+    }
+}
+
+impl From<Thermometer> for DeviceItem {
+    fn from(thermometer: Thermometer) -> Self {
+        DeviceItem::Thermometer(thermometer)
+    }
+}
+
+impl From<EnvironmentSensor> for DeviceItem {
+    fn from(sensor: EnvironmentSensor) -> Self {
+        DeviceItem::EnvironmentSensor(sensor)
+    }
+}
+
+/// Device type that can be looked up in a `DeviceItem`, like
+/// `room.get_device::<Socket>("my socket")`
+pub trait DeviceKind: Sized {
+    fn from_item(item: &DeviceItem) -> Option<&Self>;
+    fn from_item_mut(item: &mut DeviceItem) -> Option<&mut Self>;
+}
+
+impl DeviceKind for Socket {
+    fn from_item(item: &DeviceItem) -> Option<&Self> {
+        match item {
+            DeviceItem::Socket(device) => Some(device),
+            _ => None,
+        }
+    }
+
+    fn from_item_mut(item: &mut DeviceItem) -> Option<&mut Self> {
+        match item {
+            DeviceItem::Socket(device) => Some(device),
+            _ => None,
+        }
+    }
+}
+
+impl DeviceKind for Thermometer {
+    fn from_item(item: &DeviceItem) -> Option<&Self> {
+        match item {
+            DeviceItem::Thermometer(device) => Some(device),
+            _ => None,
+        }
+    }
+
+    fn from_item_mut(item: &mut DeviceItem) -> Option<&mut Self> {
+        match item {
+            DeviceItem::Thermometer(device) => Some(device),
+            _ => None,
+        }
+    }
+}
+
+impl DeviceKind for EnvironmentSensor {
+    fn from_item(item: &DeviceItem) -> Option<&Self> {
+        match item {
+            DeviceItem::EnvironmentSensor(device) => Some(device),
+            _ => None,
+        }
+    }
+
+    fn from_item_mut(item: &mut DeviceItem) -> Option<&mut Self> {
+        match item {
+            DeviceItem::EnvironmentSensor(device) => Some(device),
+            _ => None,
+        }
+    }
 }
 
 pub trait DeviceInfoProvider {
     fn get_info(&self, room_name: &str, device_name: &str) -> String;
+
+    /// The device in a structured report, providers that tell errors apart
+    /// from info should override it
+    fn get_entry(&self, room_name: &str, device_name: &str) -> DeviceEntry {
+        DeviceEntry::new(device_name, self.get_info(room_name, device_name))
+    }
+}
+
+/// Provider for devices that have to be asked over the network, so reports
+/// can query all of them at once
+pub trait AsyncDeviceInfoProvider {
+    fn fetch_info<'a>(
+        &'a self,
+        room_name: &'a str,
+        device_name: &'a str,
+    ) -> BoxFuture<'a, Result<String, DeviceConnectionError>>;
 }
 
 pub trait Device {
     fn get_name(&self) -> String;
     fn get_info(&self) -> Result<String, DeviceConnectionError>;
+
+    /// Named readings of the device, like its temperature
+    fn get_values(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
 }
 
 impl Device for DeviceItem {
@@ -30,6 +173,7 @@ impl Device for DeviceItem {
         match self {
             DeviceItem::Socket(socket) => socket.get_name(),
             DeviceItem::Thermometer(thermometer) => thermometer.get_name(),
+            DeviceItem::EnvironmentSensor(sensor) => sensor.get_name(),
         }
     }
 
@@ -37,6 +181,44 @@ impl Device for DeviceItem {
         match self {
             DeviceItem::Socket(socket) => socket.get_info(),
             DeviceItem::Thermometer(thermometer) => thermometer.get_info(),
+            DeviceItem::EnvironmentSensor(sensor) => sensor.get_info(),
+        }
+    }
+
+    fn get_values(&self) -> Vec<(String, String)> {
+        match self {
+            DeviceItem::Socket(socket) => socket.get_values(),
+            DeviceItem::Thermometer(thermometer) => thermometer.get_values(),
+            DeviceItem::EnvironmentSensor(sensor) => sensor.get_values(),
         }
     }
 }
+
+#[cfg(test)]
+mod test_device {
+    use super::*;
+
+    #[cfg(feature = "network")]
+    #[test]
+    fn test_stale_thermometer_times_out() {
+        let error = ThermometerError::Stale {
+            since_last_reading: Some(std::time::Duration::from_secs(30)),
+        };
+
+        assert!(matches!(
+            DeviceConnectionError::from(error),
+            DeviceConnectionError::TimedOutError
+        ));
+    }
+
+    #[test]
+    fn test_device_kind() {
+        let mut item = DeviceItem::from(Socket::new("my socket", "Kitchen", false, 2.0));
+
+        assert_eq!(Thermometer::from_item(&item), None);
+
+        Socket::from_item_mut(&mut item).unwrap().status = true;
+
+        assert!(Socket::from_item(&item).unwrap().status);
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thermometer_udp::metric::Metric;
+use thermometer_udp::temperature::TemperatureUnit;
+
+use super::device::{Device, DeviceConnectionError};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct EnvironmentSensor {
+    pub name: String,
+    #[serde(default)]
+    pub parent_room: String,
+
+    pub status: bool,
+    /// Latest value of every reported metric, temperatures in Celsius
+    #[serde(default, with = "metric_map")]
+    pub metrics: Vec<(Metric, f32)>,
+    /// Unit the temperature is reported in
+    #[serde(default)]
+    pub unit: TemperatureUnit,
+}
+
+// Metrics are written as a map of metric names to values
+mod metric_map {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        metrics: &[(Metric, f32)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        metrics
+            .iter()
+            .copied()
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(Metric, f32)>, D::Error> {
+        let metrics = BTreeMap::<Metric, f32>::deserialize(deserializer)?;
+
+        Ok(metrics.into_iter().collect())
+    }
+}
+
+impl EnvironmentSensor {
+    pub fn new(name: &str, parent_room: &str, status: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            parent_room: parent_room.to_string(),
+            status,
+            metrics: Vec::new(),
+            unit: TemperatureUnit::default(),
+        }
+    }
+
+    pub fn with_metric(mut self, metric: Metric, value: f32) -> Self {
+        self.set_metric(metric, value);
+        self
+    }
+
+    pub fn with_unit(mut self, unit: TemperatureUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn set_metric(&mut self, metric: Metric, value: f32) {
+        match self.metrics.iter_mut().find(|(known, _)| *known == metric) {
+            Some(known) => known.1 = value,
+            None => {
+                self.metrics.push((metric, value));
+                self.metrics.sort_by_key(|(metric, _)| *metric);
+            }
+        }
+    }
+
+    pub fn get_metric(&self, metric: Metric) -> Option<f32> {
+        self.metrics
+            .iter()
+            .find(|(known, _)| *known == metric)
+            .map(|(_, value)| *value)
+    }
+}
+
+impl Device for EnvironmentSensor {
+    fn get_name(&self) -> String {
+        self.name.to_string()
+    }
+
+    fn get_info(&self) -> Result<String, DeviceConnectionError> {
+        if self.metrics.is_empty() {
+            return Ok(format!("{} has no readings", self.name));
+        }
+
+        let values: Vec<_> = self
+            .metrics
+            .iter()
+            .map(|(metric, value)| format!("{} is {}", metric, metric.format(*value, self.unit)))
+            .collect();
+
+        let res = format!("{}: {}", self.name, values.join(", "));
+
+        Ok(res)
+    }
+
+    fn get_values(&self) -> Vec<(String, String)> {
+        self.metrics
+            .iter()
+            .map(|(metric, value)| (metric.to_string(), metric.format(*value, self.unit)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_environment_sensor {
+    use super::*;
+
+    #[test]
+    fn test_get_name() {
+        let expected_name = "my_sensor";
+        let sensor = EnvironmentSensor::new("my_sensor", "Kitchen", true);
+
+        assert_eq!(sensor.get_name(), expected_name);
+    }
+
+    #[test]
+    fn test_get_info() {
+        let expected_device_info = "my_sensor: temperature is 77°F, humidity is 40%, CO2 is 600ppm";
+        let sensor = EnvironmentSensor::new("my_sensor", "Kitchen", true)
+            .with_metric(Metric::Co2, 600.0)
+            .with_metric(Metric::Temperature, 25.0)
+            .with_metric(Metric::Humidity, 40.0)
+            .with_unit(TemperatureUnit::Fahrenheit);
+
+        assert_eq!(sensor.get_info().unwrap(), expected_device_info);
+    }
+
+    #[test]
+    fn test_get_info_without_readings() {
+        let sensor = EnvironmentSensor::new("my_sensor", "Kitchen", true);
+
+        assert_eq!(sensor.get_info().unwrap(), "my_sensor has no readings");
+    }
+}
@@ -1,3 +1,6 @@
 pub mod device;
+pub mod environment_sensor;
+#[cfg(feature = "network")]
+pub mod network;
 pub mod socket;
 pub mod thermometer;
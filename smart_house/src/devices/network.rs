@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use socket_tcp::client::{Client, ConnectionError};
+use socket_tcp::response::{ErrorCode, SocketStatus};
+use thermometer_udp::temperature::TemperatureUnit;
+use thermometer_udp::thermometer::{Thermometer, ThermometerConfig};
+use tokio::runtime::{Handle, Runtime};
+
+use super::device::{AsyncDeviceInfoProvider, DeviceConnectionError, DeviceInfoProvider};
+use crate::report::model::DeviceEntry;
+
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+enum Endpoint {
+    Socket { client: Client, socket_name: String },
+    Thermometer(Thermometer),
+}
+
+/// Reports the live state of socket-tcp and thermometer-udp devices. The
+/// thermometers run on a runtime of the provider, which the blocking methods
+/// and `DeviceInfoProvider` block on. From async code they return an error,
+/// use their async counterparts and `AsyncDeviceInfoProvider` there.
+pub struct NetworkDeviceProvider {
+    /// Time a device has to answer before it is reported as timed out
+    pub timeout: Duration,
+    /// Unit temperatures are reported in
+    pub unit: TemperatureUnit,
+
+    endpoints: HashMap<(String, String), Endpoint>,
+    runtime: Option<Runtime>,
+}
+
+impl NetworkDeviceProvider {
+    pub fn new() -> Result<Self, DeviceConnectionError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| DeviceConnectionError::ConnectionError(e.to_string()))?;
+
+        Ok(Self {
+            timeout: DEFAULT_QUERY_TIMEOUT,
+            unit: TemperatureUnit::default(),
+            endpoints: HashMap::new(),
+            runtime: Some(runtime),
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_unit(mut self, unit: TemperatureUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Reports `device_name` as the socket `socket_name` served by `client`'s server
+    pub fn add_socket(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        client: Client,
+        socket_name: &str,
+    ) {
+        let endpoint = Endpoint::Socket {
+            client,
+            socket_name: socket_name.to_string(),
+        };
+
+        self.endpoints
+            .insert((room_name.to_string(), device_name.to_string()), endpoint);
+    }
+
+    /// Starts receiving readings for `device_name` on `address`
+    pub fn add_thermometer(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        address: &str,
+    ) -> Result<(), DeviceConnectionError> {
+        self.add_thermometer_with_config(
+            room_name,
+            device_name,
+            address,
+            ThermometerConfig::default(),
+        )
+    }
+
+    pub fn add_thermometer_with_config(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        address: &str,
+        config: ThermometerConfig,
+    ) -> Result<(), DeviceConnectionError> {
+        check_not_async("add_thermometer_async")?;
+
+        let thermometer = self.runtime().block_on(Thermometer::with_config(
+            device_name.to_string(),
+            address.to_string(),
+            config,
+        ))?;
+
+        self.endpoints.insert(
+            (room_name.to_string(), device_name.to_string()),
+            Endpoint::Thermometer(thermometer),
+        );
+
+        Ok(())
+    }
+
+    /// `add_thermometer_with_config` for async code
+    pub async fn add_thermometer_async(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        address: &str,
+        config: ThermometerConfig,
+    ) -> Result<(), DeviceConnectionError> {
+        let thermometer = self
+            .runtime()
+            .spawn(Thermometer::with_config(
+                device_name.to_string(),
+                address.to_string(),
+                config,
+            ))
+            .await
+            .map_err(|e| DeviceConnectionError::ConnectionError(e.to_string()))??;
+
+        self.endpoints.insert(
+            (room_name.to_string(), device_name.to_string()),
+            Endpoint::Thermometer(thermometer),
+        );
+
+        Ok(())
+    }
+
+    /// Queries the device, rendered like the in-memory devices render themselves
+    pub fn get_device_info(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<String, DeviceConnectionError> {
+        check_not_async("query")?;
+
+        self.runtime().block_on(self.query(room_name, device_name))
+    }
+
+    /// `get_device_info` for async code
+    pub async fn query(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<String, DeviceConnectionError> {
+        let key = (room_name.to_string(), device_name.to_string());
+
+        match self.endpoints.get(&key) {
+            Some(Endpoint::Socket {
+                client,
+                socket_name,
+            }) => {
+                let query = self.query_socket(device_name, client, socket_name);
+
+                tokio::time::timeout(self.timeout, query)
+                    .await
+                    .map_err(|_| DeviceConnectionError::TimedOutError)?
+            }
+            Some(Endpoint::Thermometer(thermometer)) => Ok(format!(
+                "{} temperature is {}",
+                device_name,
+                thermometer.temperature()?.display_in(self.unit)
+            )),
+            None => Err(DeviceConnectionError::NotFoundError(
+                device_name.to_string(),
+            )),
+        }
+    }
+
+    async fn query_socket(
+        &self,
+        device_name: &str,
+        client: &Client,
+        socket_name: &str,
+    ) -> Result<String, DeviceConnectionError> {
+        let device_error = |e: ConnectionError| match e {
+            ConnectionError::ServerError(ErrorCode::SocketNotFound, _) => {
+                DeviceConnectionError::NotFoundError(device_name.to_string())
+            }
+            e => e.into(),
+        };
+
+        let status = client.get_status(socket_name).await.map_err(device_error)?;
+        let power = client
+            .get_power_consumption(socket_name)
+            .await
+            .map_err(device_error)?;
+
+        let text_status = match status {
+            SocketStatus::On => "On",
+            SocketStatus::Off => "Off",
+        };
+
+        Ok(format!(
+            "{} is {}. Power consumption is {}",
+            device_name,
+            text_status,
+            power.to_watts()
+        ))
+    }
+
+    fn runtime(&self) -> &Runtime {
+        self.runtime
+            .as_ref()
+            .expect("Runtime is only taken when the provider is dropped")
+    }
+}
+
+// Blocking on the provider's runtime panics inside another runtime
+fn check_not_async(alternative: &str) -> Result<(), DeviceConnectionError> {
+    match Handle::try_current() {
+        Ok(_) => Err(DeviceConnectionError::ConnectionError(format!(
+            "Cannot block from async code, use {} instead",
+            alternative
+        ))),
+        Err(_) => Ok(()),
+    }
+}
+
+// Thermometers keep receiving on the provider's runtime, which may be dropped
+// in async code, where a blocking shutdown isn't allowed
+impl Drop for NetworkDeviceProvider {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl DeviceInfoProvider for NetworkDeviceProvider {
+    fn get_info(&self, room_name: &str, device_name: &str) -> String {
+        match self.get_device_info(room_name, device_name) {
+            Ok(info) => info,
+            Err(e) => format!("{}: {}", device_name, e),
+        }
+    }
+
+    fn get_entry(&self, room_name: &str, device_name: &str) -> DeviceEntry {
+        DeviceEntry::from_result(device_name, self.get_device_info(room_name, device_name))
+    }
+}
+
+impl AsyncDeviceInfoProvider for NetworkDeviceProvider {
+    fn fetch_info<'a>(
+        &'a self,
+        room_name: &'a str,
+        device_name: &'a str,
+    ) -> BoxFuture<'a, Result<String, DeviceConnectionError>> {
+        Box::pin(self.query(room_name, device_name))
+    }
+}
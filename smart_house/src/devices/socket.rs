@@ -1,8 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 use super::device::{Device, DeviceConnectionError};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Socket {
     pub name: String,
+    #[serde(default)]
     pub parent_room: String,
 
     pub status: bool,
@@ -46,6 +49,21 @@ impl Device for Socket {
 
         Ok(res)
     }
+
+    fn get_values(&self) -> Vec<(String, String)> {
+        let text_status = match &self.status {
+            true => "On",
+            false => "Off",
+        };
+
+        vec![
+            ("state".to_string(), text_status.to_string()),
+            (
+                "power consumption".to_string(),
+                self.power_consumption.to_string(),
+            ),
+        ]
+    }
 }
 
 #[cfg(test)]
@@ -1,23 +1,51 @@
+use serde::{Deserialize, Serialize};
+use thermometer_udp::temperature::{Calibration, Temperature, TemperatureUnit};
+
 use super::device::{Device, DeviceConnectionError};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Thermometer {
     pub name: String,
+    #[serde(default)]
     pub parent_room: String,
 
     pub status: bool,
-    pub temperature: f32,
+    pub temperature: Temperature,
+    /// Correction applied by `set_temperature`
+    #[serde(default)]
+    pub calibration: Calibration,
+    /// Unit the temperature is reported in
+    #[serde(default)]
+    pub unit: TemperatureUnit,
 }
 
 impl Thermometer {
+    /// `temperature` is in Celsius
     pub fn new(name: &str, parent_room: &str, status: bool, temperature: f32) -> Self {
         Self {
             name: name.to_string(),
             parent_room: parent_room.to_string(),
             status,
-            temperature,
+            temperature: Temperature::from_celsius(temperature),
+            calibration: Calibration::default(),
+            unit: TemperatureUnit::default(),
         }
     }
+
+    pub fn with_unit(mut self, unit: TemperatureUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Stores a raw sensor reading, corrected by the calibration
+    pub fn set_temperature(&mut self, raw: Temperature) {
+        self.temperature = self.calibration.apply(raw);
+    }
 }
 
 impl Device for Thermometer {
@@ -26,10 +54,21 @@ impl Device for Thermometer {
     }
 
     fn get_info(&self) -> Result<String, DeviceConnectionError> {
-        let res = format!("{} temperature is {}", self.name, self.temperature);
+        let res = format!(
+            "{} temperature is {}",
+            self.name,
+            self.temperature.display_in(self.unit)
+        );
 
         Ok(res)
     }
+
+    fn get_values(&self) -> Vec<(String, String)> {
+        vec![(
+            "temperature".to_string(),
+            self.temperature.display_in(self.unit).to_string(),
+        )]
+    }
 }
 
 #[cfg(test)]
@@ -46,9 +85,28 @@ mod test_thermometer {
 
     #[test]
     fn test_get_info() {
-        let expected_device_info = "my_thermometer temperature is 26";
+        let expected_device_info = "my_thermometer temperature is 26°C";
         let thermo = Thermometer::new("my_thermometer", "Kitchen", true, 26.0);
 
         assert_eq!(thermo.get_info().unwrap(), expected_device_info);
     }
+
+    #[test]
+    fn test_get_info_in_fahrenheit() {
+        let expected_device_info = "my_thermometer temperature is 77°F";
+        let thermo = Thermometer::new("my_thermometer", "Kitchen", true, 25.0)
+            .with_unit(TemperatureUnit::Fahrenheit);
+
+        assert_eq!(thermo.get_info().unwrap(), expected_device_info);
+    }
+
+    #[test]
+    fn test_set_temperature_applies_calibration() {
+        let mut thermo = Thermometer::new("my_thermometer", "Kitchen", true, 0.0)
+            .with_calibration(Calibration::new(1.5, 1.0));
+
+        thermo.set_temperature(Temperature::from_celsius(20.0));
+
+        assert_eq!(thermo.temperature, Temperature::from_celsius(21.5));
+    }
 }
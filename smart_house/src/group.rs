@@ -0,0 +1,405 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    devices::device::{DeviceInfoProvider, DeviceKind},
+    house::{House, HouseError},
+    report::model::Report,
+    room::Room,
+};
+
+/// Named set of rooms, used for floors and zones
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct RoomGroup {
+    pub name: String,
+    #[serde(default)]
+    pub room_names: Vec<String>,
+}
+
+impl RoomGroup {
+    pub fn new(name: &str, room_names: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            room_names,
+        }
+    }
+
+    pub fn contains(&self, room_name: &str) -> bool {
+        self.room_names.iter().any(|r| r == room_name)
+    }
+}
+
+/// Part of the house that reports and bulk operations cover
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Scope {
+    House,
+    Floor(String),
+    Zone(String),
+}
+
+impl Scope {
+    pub fn floor(name: &str) -> Self {
+        Scope::Floor(name.to_string())
+    }
+
+    pub fn zone(name: &str) -> Self {
+        Scope::Zone(name.to_string())
+    }
+}
+
+impl House {
+    pub fn floors(&self) -> &Vec<RoomGroup> {
+        &self.floors
+    }
+
+    pub fn zones(&self) -> &Vec<RoomGroup> {
+        &self.zones
+    }
+
+    pub fn floor(&self, floor_name: &str) -> Option<&RoomGroup> {
+        self.floors.iter().find(|f| f.name == floor_name)
+    }
+
+    pub fn zone(&self, zone_name: &str) -> Option<&RoomGroup> {
+        self.zones.iter().find(|z| z.name == zone_name)
+    }
+
+    /// Floor the room is on
+    pub fn floor_of(&self, room_name: &str) -> Option<&RoomGroup> {
+        self.floors.iter().find(|f| f.contains(room_name))
+    }
+
+    /// Zones the room belongs to
+    pub fn zones_of(&self, room_name: &str) -> Vec<&RoomGroup> {
+        self.zones
+            .iter()
+            .filter(|z| z.contains(room_name))
+            .collect()
+    }
+
+    /// Adds the floor, its rooms must exist and not be on another floor
+    pub fn add_floor(&mut self, floor: RoomGroup) -> Result<(), HouseError> {
+        if self.floor(&floor.name).is_some() {
+            return Err(HouseError::TryingToAddAnExistingFloor(floor.name));
+        }
+
+        let room_names = floor.room_names.clone();
+        self.floors.push(RoomGroup::new(&floor.name, Vec::new()));
+
+        let result = room_names
+            .into_iter()
+            .try_for_each(|r| self.add_room_to_floor(&floor.name, &r));
+
+        if result.is_err() {
+            self.floors.pop();
+        }
+
+        result
+    }
+
+    /// Adds the zone, its rooms must exist
+    pub fn add_zone(&mut self, zone: RoomGroup) -> Result<(), HouseError> {
+        if self.zone(&zone.name).is_some() {
+            return Err(HouseError::TryingToAddAnExistingZone(zone.name));
+        }
+
+        if let Some(room_name) = zone.room_names.iter().find(|r| !self.is_room_exist(r)) {
+            return Err(HouseError::RoomNotFound(room_name.to_string()));
+        }
+
+        let mut room_names = Vec::new();
+        for room_name in zone.room_names {
+            if !room_names.contains(&room_name) {
+                room_names.push(room_name);
+            }
+        }
+
+        self.zones.push(RoomGroup::new(&zone.name, room_names));
+
+        Ok(())
+    }
+
+    pub fn add_room_to_floor(
+        &mut self,
+        floor_name: &str,
+        room_name: &str,
+    ) -> Result<(), HouseError> {
+        if !self.is_room_exist(room_name) {
+            return Err(HouseError::RoomNotFound(room_name.to_string()));
+        }
+
+        if let Some(floor) = self.floor_of(room_name) {
+            return Err(HouseError::RoomAlreadyOnFloor(
+                room_name.to_string(),
+                floor.name.to_string(),
+            ));
+        }
+
+        let floor = self
+            .floors
+            .iter_mut()
+            .find(|f| f.name == floor_name)
+            .ok_or_else(|| HouseError::FloorNotFound(floor_name.to_string()))?;
+
+        floor.room_names.push(room_name.to_string());
+
+        Ok(())
+    }
+
+    /// Adding a room the zone already has changes nothing
+    pub fn add_room_to_zone(&mut self, zone_name: &str, room_name: &str) -> Result<(), HouseError> {
+        if !self.is_room_exist(room_name) {
+            return Err(HouseError::RoomNotFound(room_name.to_string()));
+        }
+
+        let zone = self
+            .zones
+            .iter_mut()
+            .find(|z| z.name == zone_name)
+            .ok_or_else(|| HouseError::ZoneNotFound(zone_name.to_string()))?;
+
+        if !zone.contains(room_name) {
+            zone.room_names.push(room_name.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Removes the floor, its rooms stay in the house
+    pub fn remove_floor(&mut self, floor_name: &str) -> Result<(), HouseError> {
+        let idx = self
+            .floors
+            .iter()
+            .position(|f| f.name == floor_name)
+            .ok_or_else(|| HouseError::FloorNotFound(floor_name.to_string()))?;
+
+        self.floors.remove(idx);
+
+        Ok(())
+    }
+
+    /// Removes the zone, its rooms stay in the house
+    pub fn remove_zone(&mut self, zone_name: &str) -> Result<(), HouseError> {
+        let idx = self
+            .zones
+            .iter()
+            .position(|z| z.name == zone_name)
+            .ok_or_else(|| HouseError::ZoneNotFound(zone_name.to_string()))?;
+
+        self.zones.remove(idx);
+
+        Ok(())
+    }
+
+    /// Rooms of the scope, in house order
+    pub fn rooms_in(&self, scope: &Scope) -> Result<Vec<&Room>, HouseError> {
+        let group = match scope {
+            Scope::House => return Ok(self.get_rooms().iter().collect()),
+            Scope::Floor(name) => self
+                .floor(name)
+                .ok_or_else(|| HouseError::FloorNotFound(name.to_string()))?,
+            Scope::Zone(name) => self
+                .zone(name)
+                .ok_or_else(|| HouseError::ZoneNotFound(name.to_string()))?,
+        };
+
+        Ok(self
+            .get_rooms()
+            .iter()
+            .filter(|room| group.contains(&room.name))
+            .collect())
+    }
+
+    /// `build_report` of the rooms in the scope
+    pub fn build_scoped_report<T: DeviceInfoProvider>(
+        &self,
+        scope: &Scope,
+        provider: &T,
+    ) -> Result<Report, HouseError> {
+        Ok(self.build_report_of(self.rooms_in(scope)?, provider))
+    }
+
+    /// Calls `f` with every owned `T` in the scope, like every socket on a
+    /// floor, and returns how many there were
+    pub fn for_each_device_in<T: DeviceKind>(
+        &mut self,
+        scope: &Scope,
+        mut f: impl FnMut(&mut T),
+    ) -> Result<usize, HouseError> {
+        let room_names: Vec<_> = self
+            .rooms_in(scope)?
+            .into_iter()
+            .map(|room| room.name.to_string())
+            .collect();
+
+        let devices = self
+            .rooms
+            .iter_mut()
+            .filter(|room| room_names.contains(&room.name))
+            .flat_map(|room| room.devices_mut())
+            .filter_map(T::from_item_mut);
+
+        let mut count = 0;
+        for device in devices {
+            f(device);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test_group {
+    use crate::devices::{socket::Socket, thermometer::Thermometer};
+    use crate::report::render::ReportFormat;
+
+    use super::*;
+
+    fn house() -> Result<House, HouseError> {
+        let mut house = House::new("My house");
+
+        house.add_room(Room::with_devices(
+            "Kitchen",
+            vec![
+                Socket::new("kettle", "Kitchen", true, 1500.0).into(),
+                Thermometer::new("thermometer", "Kitchen", true, 24.0).into(),
+            ],
+        )?)?;
+        house.add_room(Room::with_devices(
+            "Bedroom",
+            vec![Socket::new("lamp", "Bedroom", true, 40.0).into()],
+        )?)?;
+        house.add_room(Room::with_devices(
+            "Attic",
+            vec![Socket::new("heater", "Attic", true, 2000.0).into()],
+        )?)?;
+
+        house.add_floor(RoomGroup::new("Ground floor", vec!["Kitchen".to_string()]))?;
+        house.add_floor(RoomGroup::new(
+            "First floor",
+            vec!["Bedroom".to_string(), "Attic".to_string()],
+        ))?;
+        house.add_zone(RoomGroup::new(
+            "Heating zone north",
+            vec!["Attic".to_string(), "Kitchen".to_string()],
+        ))?;
+
+        Ok(house)
+    }
+
+    #[test]
+    fn test_add_floor_error() -> Result<(), HouseError> {
+        let mut house = house()?;
+
+        assert_eq!(
+            house.add_floor(RoomGroup::new("Ground floor", Vec::new())),
+            Err(HouseError::TryingToAddAnExistingFloor(
+                "Ground floor".to_string()
+            ))
+        );
+
+        let result = house.add_floor(RoomGroup::new(
+            "Basement",
+            vec!["Cellar".to_string(), "Kitchen".to_string()],
+        ));
+
+        assert_eq!(result, Err(HouseError::RoomNotFound("Cellar".to_string())));
+        assert!(house.floor("Basement").is_none());
+
+        let result = house.add_floor(RoomGroup::new("Basement", vec!["Kitchen".to_string()]));
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Room \"Kitchen\" is already on floor \"Ground floor\""
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zones_overlap_floors() -> Result<(), HouseError> {
+        let house = house()?;
+
+        assert_eq!(house.floor_of("Attic").unwrap().name, "First floor");
+        assert_eq!(house.zones_of("Kitchen").len(), 1);
+
+        let rooms: Vec<_> = house
+            .rooms_in(&Scope::zone("Heating zone north"))?
+            .iter()
+            .map(|room| room.get_name())
+            .collect();
+
+        assert_eq!(rooms, vec!["Kitchen", "Attic"]);
+        assert_eq!(
+            house.rooms_in(&Scope::floor("Roof")),
+            Err(HouseError::FloorNotFound("Roof".to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scoped_report() -> Result<(), HouseError> {
+        let house = house()?;
+
+        let report = house.build_scoped_report(&Scope::floor("First floor"), &house)?;
+
+        let expected_report = "House: My house\nBedroom:\nlamp is On. Power consumption is 40\n\
+            Attic:\nheater is On. Power consumption is 2000";
+
+        assert_eq!(report.render(ReportFormat::Text), expected_report);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_each_device_in() -> Result<(), HouseError> {
+        let mut house = house()?;
+
+        let count = house
+            .for_each_device_in(&Scope::zone("Heating zone north"), |socket: &mut Socket| {
+                socket.status = false
+            })?;
+
+        assert_eq!(count, 2);
+        assert!(
+            !house
+                .get_device::<Socket>("Kitchen", "kettle")
+                .unwrap()
+                .status
+        );
+        assert!(
+            !house
+                .get_device::<Socket>("Attic", "heater")
+                .unwrap()
+                .status
+        );
+        assert!(
+            house
+                .get_device::<Socket>("Bedroom", "lamp")
+                .unwrap()
+                .status
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_and_rename_room() -> Result<(), HouseError> {
+        let mut house = house()?;
+
+        house.rename_room("Attic", "Loft")?;
+        house.remove_room("Kitchen")?;
+
+        assert_eq!(
+            house.floor("Ground floor").unwrap().room_names,
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            house.zone("Heating zone north").unwrap().room_names,
+            vec!["Loft".to_string()]
+        );
+
+        Ok(())
+    }
+}
@@ -1,11 +1,33 @@
-use thiserror::Error;
-
-use crate::{devices::device::DeviceInfoProvider, room::Room};
+use std::time::Duration;
 
-#[derive(Debug)]
+use futures::future;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::time::Instant;
+
+use crate::{
+    devices::device::{
+        AsyncDeviceInfoProvider, Device, DeviceConnectionError, DeviceInfoProvider, DeviceItem,
+        DeviceKind,
+    },
+    group::RoomGroup,
+    report::{
+        model::{DeviceEntry, Report, RoomReport},
+        render::{ReportFormat, TextRenderer},
+    },
+    room::Room,
+};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct House {
     name: String,
-    rooms: Vec<Room>,
+    pub(crate) rooms: Vec<Room>,
+    /// Every room is on one floor at most
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) floors: Vec<RoomGroup>,
+    /// Zones may share rooms and span floors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) zones: Vec<RoomGroup>,
 }
 
 #[derive(Debug, PartialEq, Eq, Error)]
@@ -14,6 +36,20 @@ pub enum HouseError {
     TryingToAddAnExistingRoom(String),
     #[error("Device with name {:?} already exists", .0)]
     TryingToAddAnExistingDevice(String),
+    #[error("Cannot find room with name {:?}", .0)]
+    RoomNotFound(String),
+    #[error("Cannot find device with name {:?}", .0)]
+    DeviceNotFound(String),
+    #[error("Floor with name {:?} already exists", .0)]
+    TryingToAddAnExistingFloor(String),
+    #[error("Zone with name {:?} already exists", .0)]
+    TryingToAddAnExistingZone(String),
+    #[error("Cannot find floor with name {:?}", .0)]
+    FloorNotFound(String),
+    #[error("Cannot find zone with name {:?}", .0)]
+    ZoneNotFound(String),
+    #[error("Room {:?} is already on floor {:?}", .0, .1)]
+    RoomAlreadyOnFloor(String, String),
 }
 
 impl House {
@@ -21,6 +57,8 @@ impl House {
         Self {
             rooms: Vec::new(),
             name: name.to_string(),
+            floors: Vec::new(),
+            zones: Vec::new(),
         }
     }
 
@@ -46,52 +84,285 @@ impl House {
         rooms.into_iter().try_for_each(|r| Self::add_room(self, r))
     }
 
-    pub fn remove_room(&mut self, room_name: &str) {
+    pub fn remove_room(&mut self, room_name: &str) -> Result<(), HouseError> {
         let idx = self
             .get_rooms()
             .iter()
             .position(|r| r.get_name() == room_name)
-            .unwrap();
+            .ok_or_else(|| HouseError::RoomNotFound(room_name.to_string()))?;
 
         self.rooms.remove(idx);
-    }
 
-    pub fn devices(&self, room_name: &str) -> &Vec<String> {
-        let fitting_room = self
-            .get_rooms()
-            .iter()
-            .find(|r| r.name == room_name)
-            .unwrap();
+        for group in self.floors.iter_mut().chain(&mut self.zones) {
+            group.room_names.retain(|r| r != room_name);
+        }
 
-        &fitting_room.device_names
+        Ok(())
     }
 
-    pub fn create_report_lines<T: DeviceInfoProvider>(&self, provider: &T) -> Vec<String> {
-        let mut report = Vec::new();
+    /// Renames the room, its owned devices get the new name as their parent room
+    pub fn rename_room(&mut self, room_name: &str, new_name: &str) -> Result<(), HouseError> {
+        if room_name != new_name && self.is_room_exist(new_name) {
+            return Err(HouseError::TryingToAddAnExistingRoom(new_name.to_string()));
+        }
+
+        let room = self
+            .room_mut(room_name)
+            .ok_or_else(|| HouseError::RoomNotFound(room_name.to_string()))?;
 
-        report.push(format!("House: {}", self.name));
-        for room in self.get_rooms() {
-            report.push(format!("{}:", room.name));
+        room.name = new_name.to_string();
+        for device in room.devices_mut() {
+            device.set_parent_room(new_name);
+        }
 
-            for device_name in self.devices(&room.name) {
-                report.push(provider.get_info(&room.name, device_name))
+        for group in self.floors.iter_mut().chain(&mut self.zones) {
+            for name in group.room_names.iter_mut().filter(|r| *r == room_name) {
+                *name = new_name.to_string();
             }
         }
 
-        report
+        Ok(())
+    }
+
+    /// Moves the device, owned or not, to the end of another room. Nothing
+    /// changes if the other room already has a device with the name.
+    pub fn move_device(
+        &mut self,
+        from_room: &str,
+        to_room: &str,
+        device_name: &str,
+    ) -> Result<(), HouseError> {
+        let target = self
+            .room(to_room)
+            .ok_or_else(|| HouseError::RoomNotFound(to_room.to_string()))?;
+
+        if from_room == to_room {
+            return match target.is_device_exist(device_name) {
+                true => Ok(()),
+                false => Err(HouseError::DeviceNotFound(device_name.to_string())),
+            };
+        }
+
+        if target.is_device_exist(device_name) {
+            return Err(HouseError::TryingToAddAnExistingDevice(
+                device_name.to_string(),
+            ));
+        }
+
+        let device = self
+            .room_mut(from_room)
+            .ok_or_else(|| HouseError::RoomNotFound(from_room.to_string()))?
+            .take_device(device_name)?;
+
+        let target = self
+            .room_mut(to_room)
+            .expect("Target room is checked before the device is taken");
+
+        match device {
+            Some(device) => target.add_device_item(device),
+            None => target.add_device(device_name.to_string()),
+        }
+    }
+
+    pub fn room(&self, room_name: &str) -> Option<&Room> {
+        self.rooms.iter().find(|r| r.name == room_name)
+    }
+
+    pub fn room_mut(&mut self, room_name: &str) -> Option<&mut Room> {
+        self.rooms.iter_mut().find(|r| r.name == room_name)
+    }
+
+    pub fn device(&self, room_name: &str, device_name: &str) -> Option<&DeviceItem> {
+        self.room(room_name)?.device(device_name)
+    }
+
+    pub fn device_mut(&mut self, room_name: &str, device_name: &str) -> Option<&mut DeviceItem> {
+        self.room_mut(room_name)?.device_mut(device_name)
+    }
+
+    pub fn get_device<T: DeviceKind>(&self, room_name: &str, device_name: &str) -> Option<&T> {
+        self.room(room_name)?.get_device(device_name)
+    }
+
+    pub fn get_device_mut<T: DeviceKind>(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Option<&mut T> {
+        self.room_mut(room_name)?.get_device_mut(device_name)
+    }
+
+    pub fn devices(&self, room_name: &str) -> Result<Vec<String>, HouseError> {
+        let fitting_room = self
+            .room(room_name)
+            .ok_or_else(|| HouseError::RoomNotFound(room_name.to_string()))?;
+
+        Ok(fitting_room.device_names())
+    }
+
+    /// Report with an entry for every device, in room order
+    pub fn build_report<T: DeviceInfoProvider>(&self, provider: &T) -> Report {
+        self.build_report_of(self.get_rooms(), provider)
+    }
+
+    pub(crate) fn build_report_of<'a, T: DeviceInfoProvider>(
+        &self,
+        rooms: impl IntoIterator<Item = &'a Room>,
+        provider: &T,
+    ) -> Report {
+        let rooms = rooms
+            .into_iter()
+            .map(|room| RoomReport {
+                name: room.name.to_string(),
+                devices: room
+                    .device_names()
+                    .iter()
+                    .map(|device_name| provider.get_entry(&room.name, device_name))
+                    .collect(),
+            })
+            .collect();
+
+        Report {
+            house: self.name.to_string(),
+            rooms,
+        }
+    }
+
+    pub fn create_report_lines<T: DeviceInfoProvider>(&self, provider: &T) -> Vec<String> {
+        TextRenderer.lines(&self.build_report(provider))
     }
 
     pub fn create_report<T: DeviceInfoProvider>(&self, provider: &T) -> String {
         self.create_report_lines(provider).join("\n")
     }
+
+    /// Report of the devices the house owns
+    pub fn report(&self) -> String {
+        self.create_report(self)
+    }
+
+    /// `report` in another format
+    pub fn render_report(&self, format: ReportFormat) -> String {
+        self.build_report(self).render(format)
+    }
+
+    /// Same report as `build_report`, but all devices are queried at once.
+    /// Devices that don't answer within `deadline` are reported as
+    /// unavailable.
+    pub async fn build_report_async<T: AsyncDeviceInfoProvider>(
+        &self,
+        provider: &T,
+        deadline: Duration,
+    ) -> Report {
+        let deadline = Instant::now() + deadline;
+
+        let queries = self.get_rooms().iter().flat_map(|room| {
+            room.device_names()
+                .into_iter()
+                .map(move |device_name| async move {
+                    let query = provider.fetch_info(&room.name, &device_name);
+
+                    match tokio::time::timeout_at(deadline, query).await {
+                        Ok(info) => DeviceEntry::from_result(&device_name, info),
+                        Err(_) => DeviceEntry::unavailable(&device_name),
+                    }
+                })
+        });
+        let mut entries = future::join_all(queries).await.into_iter();
+
+        let rooms = self
+            .get_rooms()
+            .iter()
+            .map(|room| RoomReport {
+                name: room.name.to_string(),
+                devices: entries.by_ref().take(room.device_names().len()).collect(),
+            })
+            .collect();
+
+        Report {
+            house: self.name.to_string(),
+            rooms,
+        }
+    }
+
+    pub async fn create_report_lines_async<T: AsyncDeviceInfoProvider>(
+        &self,
+        provider: &T,
+        deadline: Duration,
+    ) -> Vec<String> {
+        TextRenderer.lines(&self.build_report_async(provider, deadline).await)
+    }
+
+    pub async fn create_report_async<T: AsyncDeviceInfoProvider>(
+        &self,
+        provider: &T,
+        deadline: Duration,
+    ) -> String {
+        self.create_report_lines_async(provider, deadline)
+            .await
+            .join("\n")
+    }
+}
+
+/// Reports the owned devices, devices the house only knows by name aren't found
+impl DeviceInfoProvider for House {
+    fn get_info(&self, room_name: &str, device_name: &str) -> String {
+        let info = match self.device(room_name, device_name) {
+            Some(device) => device.get_info(),
+            None => Err(DeviceConnectionError::NotFoundError(
+                device_name.to_string(),
+            )),
+        };
+
+        match info {
+            Ok(info) => info,
+            Err(e) => format!("{}: {}", device_name, e),
+        }
+    }
+
+    fn get_entry(&self, room_name: &str, device_name: &str) -> DeviceEntry {
+        match self.device(room_name, device_name) {
+            Some(device) => DeviceEntry::from_result(device_name, device.get_info())
+                .with_values(device.get_values()),
+            None => DeviceEntry::error(
+                device_name,
+                &DeviceConnectionError::NotFoundError(device_name.to_string()),
+            ),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test_house {
+    use futures::future::BoxFuture;
+
+    use crate::devices::socket::Socket;
     use crate::utils::are_vecs_equal;
 
     use super::*;
 
+    // Answers after as many milliseconds as the number in the device name
+    struct SlowProvider;
+
+    impl AsyncDeviceInfoProvider for SlowProvider {
+        fn fetch_info<'a>(
+            &'a self,
+            _room_name: &'a str,
+            device_name: &'a str,
+        ) -> BoxFuture<'a, Result<String, DeviceConnectionError>> {
+            Box::pin(async move {
+                let delay = device_name
+                    .trim_start_matches(char::is_alphabetic)
+                    .parse()
+                    .map_err(|_| DeviceConnectionError::NotFoundError(device_name.to_string()))?;
+
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+
+                Ok(format!("{} answered", device_name))
+            })
+        }
+    }
+
     #[test]
     fn test_get_rooms() {
         let room_1 = Room::new("Kitchen", Vec::new());
@@ -152,11 +423,26 @@ mod test_house {
 
         assert_eq!(house.get_rooms().len(), 1);
 
-        house.remove_room("Kitchen");
+        let result = house.remove_room("Kitchen");
 
+        assert_eq!(result, Ok(()));
         assert_eq!(house.get_rooms().len(), 0);
     }
 
+    #[test]
+    fn test_remove_room_error() {
+        let mut house = House::new("My house");
+
+        let result = house.remove_room("Kitchen");
+
+        assert_eq!(result, Err(HouseError::RoomNotFound("Kitchen".to_string())));
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Cannot find room with name \"Kitchen\"",
+        );
+    }
+
     #[test]
     fn test_devices() {
         let mut house = House::new("My house");
@@ -167,13 +453,175 @@ mod test_house {
         let expected_devices = vec!["socket".to_string()];
 
         assert_eq!(
-            house.devices(&room.get_name()).len(),
+            house.devices(&room.get_name()).unwrap().len(),
             expected_devices.len()
         );
 
         assert!(are_vecs_equal(
-            house.devices(&room.get_name()),
+            &house.devices(&room.get_name()).unwrap(),
             &expected_devices
         ));
     }
+
+    #[test]
+    fn test_devices_error() {
+        let house = House::new("My house");
+
+        assert_eq!(
+            house.devices("Kitchen"),
+            Err(HouseError::RoomNotFound("Kitchen".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rename_room() -> Result<(), HouseError> {
+        let mut house = House::new("My house");
+        house.add_room(Room::with_devices(
+            "Kitchen",
+            vec![Socket::new("kettle", "Kitchen", false, 0.0).into()],
+        )?)?;
+        house.add_room(Room::new("Hallway", Vec::new()))?;
+
+        assert_eq!(
+            house.rename_room("Kitchen", "Hallway"),
+            Err(HouseError::TryingToAddAnExistingRoom("Hallway".to_string()))
+        );
+        assert_eq!(
+            house.rename_room("Bedroom", "Attic"),
+            Err(HouseError::RoomNotFound("Bedroom".to_string()))
+        );
+
+        house.rename_room("Kitchen", "Dining room")?;
+
+        assert!(!house.is_room_exist("Kitchen"));
+        assert_eq!(
+            house.device("Dining room", "kettle").unwrap().parent_room(),
+            "Dining room"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_device() -> Result<(), HouseError> {
+        let mut house = House::new("My house");
+        let mut kitchen = Room::with_devices(
+            "Kitchen",
+            vec![Socket::new("kettle", "Kitchen", false, 0.0).into()],
+        )?;
+        kitchen.add_device("radio".to_string())?;
+        house.add_room(kitchen)?;
+        house.add_room(Room::new("Hallway", vec!["radio".to_string()]))?;
+
+        house.move_device("Kitchen", "Hallway", "kettle")?;
+
+        assert!(house.device("Kitchen", "kettle").is_none());
+        assert_eq!(
+            house.device("Hallway", "kettle").unwrap().parent_room(),
+            "Hallway"
+        );
+        assert_eq!(
+            house.devices("Hallway")?,
+            vec!["radio".to_string(), "kettle".to_string()]
+        );
+
+        assert_eq!(
+            house.move_device("Kitchen", "Hallway", "radio"),
+            Err(HouseError::TryingToAddAnExistingDevice("radio".to_string()))
+        );
+        assert_eq!(
+            house.move_device("Kitchen", "Hallway", "toaster"),
+            Err(HouseError::DeviceNotFound("toaster".to_string()))
+        );
+        assert_eq!(
+            house.move_device("Kitchen", "Attic", "radio"),
+            Err(HouseError::RoomNotFound("Attic".to_string()))
+        );
+        assert_eq!(house.devices("Kitchen")?, vec!["radio".to_string()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_report_async() {
+        let mut house = House::new("My house");
+        let _r1 = house.add_room(Room::new(
+            "Kitchen",
+            vec!["kettle200".to_string(), "toaster".to_string()],
+        ));
+        let _r2 = house.add_room(Room::new(
+            "Hallway",
+            vec!["lamp200".to_string(), "radio2000".to_string()],
+        ));
+
+        let started = Instant::now();
+        let report = house
+            .create_report_async(&SlowProvider, Duration::from_millis(500))
+            .await;
+
+        let expected_report = "House: My house\nKitchen:\nkettle200 answered\n\
+            toaster: Cannot find device with name \"toaster\"\n\
+            Hallway:\nlamp200 answered\nradio2000: unavailable";
+
+        assert_eq!(report, expected_report);
+        assert!(started.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_get_device_mut() -> Result<(), HouseError> {
+        let mut house = House::new("My house");
+        house.add_room(Room::with_devices(
+            "Kitchen",
+            vec![Socket::new("kettle", "Kitchen", false, 0.0).into()],
+        )?)?;
+
+        let kettle = house.get_device_mut::<Socket>("Kitchen", "kettle").unwrap();
+        kettle.status = true;
+        kettle.power_consumption = 1500.0;
+
+        assert!(
+            house
+                .get_device::<Socket>("Kitchen", "kettle")
+                .unwrap()
+                .status
+        );
+        assert!(house.device("Hallway", "kettle").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_report() -> Result<(), HouseError> {
+        let mut house = House::new("My house");
+        let mut kitchen = Room::with_devices(
+            "Kitchen",
+            vec![Socket::new("kettle", "Kitchen", true, 1500.0).into()],
+        )?;
+        kitchen.add_device("toaster".to_string())?;
+        house.add_room(kitchen)?;
+
+        let expected_report =
+            "House: My house\nKitchen:\nkettle is On. Power consumption is 1500\n\
+            toaster: Cannot find device with name \"toaster\"";
+
+        assert_eq!(house.report(), expected_report);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_report() -> Result<(), HouseError> {
+        let mut house = House::new("My house");
+        house.add_room(Room::with_devices(
+            "Kitchen",
+            vec![Socket::new("kettle", "Kitchen", true, 1500.0).into()],
+        )?)?;
+
+        let expected_report = "room,device,status,details\n\
+            Kitchen,kettle,ok,\"state: On, power consumption: 1500\"";
+
+        assert_eq!(house.render_report(ReportFormat::Csv), expected_report);
+
+        Ok(())
+    }
 }
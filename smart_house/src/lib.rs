@@ -1,4 +1,7 @@
+pub mod config;
 pub mod devices;
+pub mod group;
 pub mod house;
+pub mod report;
 pub mod room;
 pub mod utils;
@@ -0,0 +1,174 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::render::ReportFormat;
+use crate::devices::device::DeviceConnectionError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+    Ok,
+    Error,
+    /// The device didn't answer in time
+    Unavailable,
+}
+
+impl EntryStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntryStatus::Ok => "ok",
+            EntryStatus::Error => "error",
+            EntryStatus::Unavailable => "unavailable",
+        }
+    }
+}
+
+impl fmt::Display for EntryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceEntry {
+    pub name: String,
+    pub status: EntryStatus,
+    /// Line the device describes itself with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<String>,
+    /// Readings of the device, like its temperature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<ReportValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DeviceEntry {
+    pub fn new(name: &str, info: String) -> Self {
+        Self {
+            name: name.to_string(),
+            status: EntryStatus::Ok,
+            info: Some(info),
+            values: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn with_values(mut self, values: Vec<(String, String)>) -> Self {
+        self.values = values
+            .into_iter()
+            .map(|(name, value)| ReportValue { name, value })
+            .collect();
+        self
+    }
+
+    pub fn error(name: &str, error: &DeviceConnectionError) -> Self {
+        Self {
+            name: name.to_string(),
+            status: EntryStatus::Error,
+            info: None,
+            values: Vec::new(),
+            error: Some(error.to_string()),
+        }
+    }
+
+    pub fn unavailable(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: EntryStatus::Unavailable,
+            info: None,
+            values: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn from_result(name: &str, info: Result<String, DeviceConnectionError>) -> Self {
+        match info {
+            Ok(info) => Self::new(name, info),
+            Err(e) => Self::error(name, &e),
+        }
+    }
+
+    /// The entry as a line of the plain text report
+    pub fn line(&self) -> String {
+        match (&self.info, &self.error) {
+            (Some(info), _) => info.to_string(),
+            (None, Some(error)) => format!("{}: {}", self.name, error),
+            (None, None) => format!("{}: {}", self.name, self.status),
+        }
+    }
+
+    /// The values, or the info or error without them, for a table cell
+    pub fn details(&self) -> String {
+        if !self.values.is_empty() {
+            let values: Vec<_> = self
+                .values
+                .iter()
+                .map(|value| format!("{}: {}", value.name, value.value))
+                .collect();
+
+            return values.join(", ");
+        }
+
+        self.info
+            .clone()
+            .or_else(|| self.error.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomReport {
+    pub name: String,
+    pub devices: Vec<DeviceEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    pub house: String,
+    pub rooms: Vec<RoomReport>,
+}
+
+impl Report {
+    pub fn render(&self, format: ReportFormat) -> String {
+        format.renderer().render(self)
+    }
+}
+
+#[cfg(test)]
+mod test_model {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        let error = DeviceConnectionError::NotFoundError("tv".to_string());
+
+        assert_eq!(
+            DeviceEntry::new("tv", "tv is On".to_string()).line(),
+            "tv is On"
+        );
+        assert_eq!(
+            DeviceEntry::error("tv", &error).line(),
+            "tv: Cannot find device with name \"tv\""
+        );
+        assert_eq!(DeviceEntry::unavailable("tv").line(), "tv: unavailable");
+    }
+
+    #[test]
+    fn test_details() {
+        let entry = DeviceEntry::new("kettle", "kettle is On".to_string()).with_values(vec![
+            ("state".to_string(), "On".to_string()),
+            ("power consumption".to_string(), "1500".to_string()),
+        ]);
+
+        assert_eq!(entry.details(), "state: On, power consumption: 1500");
+        assert_eq!(DeviceEntry::unavailable("tv").details(), "");
+    }
+}
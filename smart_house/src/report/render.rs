@@ -0,0 +1,271 @@
+use std::str::FromStr;
+
+use super::model::{DeviceEntry, Report};
+
+pub trait ReportRenderer {
+    fn render(&self, report: &Report) -> String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Markdown,
+    Json,
+    Csv,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn renderer(&self) -> Box<dyn ReportRenderer> {
+        match self {
+            ReportFormat::Text => Box::new(TextRenderer),
+            ReportFormat::Markdown => Box::new(MarkdownRenderer),
+            ReportFormat::Json => Box::new(JsonRenderer),
+            ReportFormat::Csv => Box::new(CsvRenderer),
+            ReportFormat::Html => Box::new(HtmlRenderer),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(ReportFormat::Text),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(format!("Unknown report format {:?}", s)),
+        }
+    }
+}
+
+/// "House: name", then every room followed by the lines of its devices
+pub struct TextRenderer;
+
+impl TextRenderer {
+    pub fn lines(&self, report: &Report) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        lines.push(format!("House: {}", report.house));
+        for room in &report.rooms {
+            lines.push(format!("{}:", room.name));
+            lines.extend(room.devices.iter().map(DeviceEntry::line));
+        }
+
+        lines
+    }
+}
+
+impl ReportRenderer for TextRenderer {
+    fn render(&self, report: &Report) -> String {
+        self.lines(report).join("\n")
+    }
+}
+
+/// A table of devices for every room
+pub struct MarkdownRenderer;
+
+impl ReportRenderer for MarkdownRenderer {
+    fn render(&self, report: &Report) -> String {
+        let escape = |cell: &str| cell.replace('|', "\\|").replace('\n', " ");
+        let mut sections = vec![format!("# {}", report.house)];
+
+        for room in &report.rooms {
+            let mut section = vec![format!("## {}", room.name), String::new()];
+
+            if room.devices.is_empty() {
+                section.push("_No devices_".to_string());
+            } else {
+                section.push("| Device | Status | Details |".to_string());
+                section.push("| --- | --- | --- |".to_string());
+                section.extend(room.devices.iter().map(|device| {
+                    format!(
+                        "| {} | {} | {} |",
+                        escape(&device.name),
+                        device.status,
+                        escape(&device.details())
+                    )
+                }));
+            }
+
+            sections.push(section.join("\n"));
+        }
+
+        sections.join("\n\n")
+    }
+}
+
+pub struct JsonRenderer;
+
+impl ReportRenderer for JsonRenderer {
+    fn render(&self, report: &Report) -> String {
+        serde_json::to_string_pretty(report).expect("Report has only string keys")
+    }
+}
+
+/// A row for every device, rooms without devices are left out
+pub struct CsvRenderer;
+
+impl ReportRenderer for CsvRenderer {
+    fn render(&self, report: &Report) -> String {
+        let escape = |field: &str| match field.contains([',', '"', '\n']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.to_string(),
+        };
+
+        let mut rows = vec!["room,device,status,details".to_string()];
+
+        for room in &report.rooms {
+            rows.extend(room.devices.iter().map(|device| {
+                [
+                    escape(&room.name),
+                    escape(&device.name),
+                    device.status.to_string(),
+                    escape(&device.details()),
+                ]
+                .join(",")
+            }));
+        }
+
+        rows.join("\n")
+    }
+}
+
+/// A fragment with a table of devices for every room, rows have the status
+/// as their class
+pub struct HtmlRenderer;
+
+impl ReportRenderer for HtmlRenderer {
+    fn render(&self, report: &Report) -> String {
+        let escape = |text: &str| {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+
+        let mut html = vec![format!("<h1>{}</h1>", escape(&report.house))];
+
+        for room in &report.rooms {
+            html.push(format!("<h2>{}</h2>", escape(&room.name)));
+
+            if room.devices.is_empty() {
+                html.push("<p>No devices</p>".to_string());
+                continue;
+            }
+
+            html.push("<table>".to_string());
+            html.push("<tr><th>Device</th><th>Status</th><th>Details</th></tr>".to_string());
+            html.extend(room.devices.iter().map(|device| {
+                format!(
+                    "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    device.status,
+                    escape(&device.name),
+                    device.status,
+                    escape(&device.details())
+                )
+            }));
+            html.push("</table>".to_string());
+        }
+
+        html.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test_render {
+    use crate::devices::device::DeviceConnectionError;
+    use crate::report::model::RoomReport;
+
+    use super::*;
+
+    fn report() -> Report {
+        let error = DeviceConnectionError::NotFoundError("radio".to_string());
+
+        Report {
+            house: "My house".to_string(),
+            rooms: vec![
+                RoomReport {
+                    name: "Kitchen".to_string(),
+                    devices: vec![
+                        DeviceEntry::new("kettle", "kettle is On".to_string()).with_values(vec![
+                            ("state".to_string(), "On".to_string()),
+                            ("power consumption".to_string(), "1500".to_string()),
+                        ]),
+                        DeviceEntry::error("radio", &error),
+                    ],
+                },
+                RoomReport {
+                    name: "Hallway".to_string(),
+                    devices: vec![DeviceEntry::unavailable("lamp")],
+                },
+                RoomReport {
+                    name: "Attic".to_string(),
+                    devices: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        let expected = "House: My house\nKitchen:\nkettle is On\n\
+            radio: Cannot find device with name \"radio\"\nHallway:\nlamp: unavailable\nAttic:";
+
+        assert_eq!(report().render(ReportFormat::Text), expected);
+    }
+
+    #[test]
+    fn test_markdown() {
+        let expected = "# My house\n\n## Kitchen\n\n\
+            | Device | Status | Details |\n| --- | --- | --- |\n\
+            | kettle | ok | state: On, power consumption: 1500 |\n\
+            | radio | error | Cannot find device with name \"radio\" |\n\n\
+            ## Hallway\n\n| Device | Status | Details |\n| --- | --- | --- |\n\
+            | lamp | unavailable |  |\n\n## Attic\n\n_No devices_";
+
+        assert_eq!(report().render(ReportFormat::Markdown), expected);
+    }
+
+    #[test]
+    fn test_json() {
+        let json = report().render(ReportFormat::Json);
+
+        assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report());
+        assert!(json.contains("\"status\": \"unavailable\""));
+    }
+
+    #[test]
+    fn test_csv() {
+        let expected = "room,device,status,details\n\
+            Kitchen,kettle,ok,\"state: On, power consumption: 1500\"\n\
+            Kitchen,radio,error,\"Cannot find device with name \"\"radio\"\"\"\n\
+            Hallway,lamp,unavailable,";
+
+        assert_eq!(report().render(ReportFormat::Csv), expected);
+    }
+
+    #[test]
+    fn test_html() {
+        let html = report().render(ReportFormat::Html);
+
+        assert!(html.starts_with("<h1>My house</h1>\n<h2>Kitchen</h2>\n<table>"));
+        assert!(html.contains(
+            "<tr class=\"error\"><td>radio</td><td>error</td>\
+            <td>Cannot find device with name &quot;radio&quot;</td></tr>"
+        ));
+        assert!(html.ends_with("<h2>Attic</h2>\n<p>No devices</p>"));
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("md".parse(), Ok(ReportFormat::Markdown));
+        assert_eq!(
+            "pdf".parse::<ReportFormat>(),
+            Err("Unknown report format \"pdf\"".to_string())
+        );
+    }
+}
@@ -1,9 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+use crate::devices::device::{Device, DeviceItem, DeviceKind};
 use crate::house::HouseError;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(from = "RoomConfig", into = "RoomConfig")]
 pub struct Room {
     pub name: String,
-    pub device_names: Vec<String>,
+    /// Every device of the room in the order it was added. Names of owned
+    /// devices come from the devices themselves, so renaming one through
+    /// `get_device_mut` renames it in the room too.
+    devices: Vec<RoomDevice>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum RoomDevice {
+    /// Device known only by name, its state comes from a provider
+    Named(String),
+    Owned(DeviceItem),
+}
+
+impl RoomDevice {
+    fn name(&self) -> String {
+        match self {
+            RoomDevice::Named(name) => name.to_string(),
+            RoomDevice::Owned(device) => device.get_name(),
+        }
+    }
+}
+
+/// Stored form of a room: the names of all its devices and the owned ones.
+/// Owned devices may leave out their name from `device_names` and their
+/// parent room, both are filled in.
+#[derive(Serialize, Deserialize)]
+struct RoomConfig {
+    name: String,
+    #[serde(default)]
+    device_names: Vec<String>,
+    #[serde(default)]
+    devices: Vec<DeviceItem>,
+}
+
+impl From<RoomConfig> for Room {
+    fn from(config: RoomConfig) -> Self {
+        let mut owned = config.devices;
+        let mut devices: Vec<RoomDevice> = config
+            .device_names
+            .into_iter()
+            .map(
+                |name| match owned.iter().position(|d| d.get_name() == name) {
+                    Some(idx) => RoomDevice::Owned(owned.remove(idx)),
+                    None => RoomDevice::Named(name),
+                },
+            )
+            .collect();
+        devices.extend(owned.into_iter().map(RoomDevice::Owned));
+
+        for device in &mut devices {
+            if let RoomDevice::Owned(device) = device {
+                if device.parent_room().is_empty() {
+                    device.set_parent_room(&config.name);
+                }
+            }
+        }
+
+        Self {
+            name: config.name,
+            devices,
+        }
+    }
+}
+
+impl From<Room> for RoomConfig {
+    fn from(room: Room) -> Self {
+        let device_names = room.device_names();
+        let devices = room
+            .devices
+            .into_iter()
+            .filter_map(|device| match device {
+                RoomDevice::Owned(device) => Some(device),
+                RoomDevice::Named(_) => None,
+            })
+            .collect();
+
+        Self {
+            name: room.name,
+            device_names,
+            devices,
+        }
+    }
 }
 
 impl Room {
@@ -14,19 +99,46 @@ impl Room {
     pub fn new(name: &str, device_names: Vec<String>) -> Self {
         Self {
             name: name.to_string(),
-            device_names,
+            devices: device_names.into_iter().map(RoomDevice::Named).collect(),
         }
     }
 
+    pub fn with_devices(name: &str, devices: Vec<DeviceItem>) -> Result<Self, HouseError> {
+        let mut room = Self::new(name, Vec::new());
+        room.add_device_items(devices)?;
+
+        Ok(room)
+    }
+
+    /// Names of every device of the room, owned or not
+    pub fn device_names(&self) -> Vec<String> {
+        self.devices.iter().map(RoomDevice::name).collect()
+    }
+
+    /// Devices the room owns
+    pub fn devices(&self) -> impl Iterator<Item = &DeviceItem> {
+        self.devices.iter().filter_map(|device| match device {
+            RoomDevice::Owned(device) => Some(device),
+            RoomDevice::Named(_) => None,
+        })
+    }
+
+    pub fn devices_mut(&mut self) -> impl Iterator<Item = &mut DeviceItem> {
+        self.devices.iter_mut().filter_map(|device| match device {
+            RoomDevice::Owned(device) => Some(device),
+            RoomDevice::Named(_) => None,
+        })
+    }
+
     pub fn is_device_exist(&self, device_name: &str) -> bool {
-        self.device_names.iter().any(|d| d == device_name)
+        self.devices.iter().any(|d| d.name() == device_name)
     }
 
     pub fn add_device(&mut self, device: String) -> Result<(), HouseError> {
         if Self::is_device_exist(self, &device) {
             Err(HouseError::TryingToAddAnExistingDevice(device))
         } else {
-            self.device_names.push(device);
+            self.devices.push(RoomDevice::Named(device));
 
             Ok(())
         }
@@ -38,19 +150,68 @@ impl Room {
             .try_for_each(|d| Self::add_device(self, d))
     }
 
-    pub fn remove_device(&mut self, device_name: &str) {
+    /// Adds the device under its own name, with the room as its parent
+    pub fn add_device_item(&mut self, device: impl Into<DeviceItem>) -> Result<(), HouseError> {
+        let mut device = device.into();
+        let device_name = device.get_name();
+
+        if self.is_device_exist(&device_name) {
+            return Err(HouseError::TryingToAddAnExistingDevice(device_name));
+        }
+
+        device.set_parent_room(&self.name);
+        self.devices.push(RoomDevice::Owned(device));
+
+        Ok(())
+    }
+
+    pub fn add_device_items(&mut self, devices: Vec<DeviceItem>) -> Result<(), HouseError> {
+        devices
+            .into_iter()
+            .try_for_each(|d| Self::add_device_item(self, d))
+    }
+
+    pub fn remove_device(&mut self, device_name: &str) -> Result<(), HouseError> {
+        self.take_device(device_name).map(|_| ())
+    }
+
+    /// Removes the device, returning it if the room owned it
+    pub fn take_device(&mut self, device_name: &str) -> Result<Option<DeviceItem>, HouseError> {
         let idx = self
-            .device_names
+            .devices
             .iter()
-            .position(|d| d == device_name)
-            .unwrap();
+            .position(|d| d.name() == device_name)
+            .ok_or_else(|| HouseError::DeviceNotFound(device_name.to_string()))?;
+
+        match self.devices.remove(idx) {
+            RoomDevice::Owned(device) => Ok(Some(device)),
+            RoomDevice::Named(_) => Ok(None),
+        }
+    }
+
+    /// The owned device, `None` for unknown devices and ones the room doesn't own
+    pub fn device(&self, device_name: &str) -> Option<&DeviceItem> {
+        self.devices().find(|d| d.get_name() == device_name)
+    }
 
-        self.device_names.remove(idx);
+    pub fn device_mut(&mut self, device_name: &str) -> Option<&mut DeviceItem> {
+        self.devices_mut().find(|d| d.get_name() == device_name)
+    }
+
+    /// The owned device if it is a `T`
+    pub fn get_device<T: DeviceKind>(&self, device_name: &str) -> Option<&T> {
+        T::from_item(self.device(device_name)?)
+    }
+
+    pub fn get_device_mut<T: DeviceKind>(&mut self, device_name: &str) -> Option<&mut T> {
+        T::from_item_mut(self.device_mut(device_name)?)
     }
 }
 
 #[cfg(test)]
 mod test_house {
+    use crate::devices::{socket::Socket, thermometer::Thermometer};
+
     use super::*;
 
     #[test]
@@ -100,7 +261,7 @@ mod test_house {
         let result = room.add_devices(vec!["tv".to_string(), "thermo".to_string()]);
 
         assert_eq!(
-            room.device_names,
+            room.device_names(),
             vec!["tv".to_string(), "thermo".to_string()]
         );
         assert_eq!(result, Ok(()));
@@ -135,10 +296,98 @@ mod test_house {
         let mut room = Room::new("My room", Vec::new());
 
         let _r = room.add_device("tv".to_string());
-        assert_eq!(room.device_names.len(), 1);
+        assert_eq!(room.device_names().len(), 1);
+
+        let result = room.remove_device("tv");
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(room.device_names().len(), 0);
+    }
+
+    #[test]
+    fn test_remove_device_error() {
+        let mut room = Room::new("My room", Vec::new());
+
+        let result = room.remove_device("tv");
+
+        assert_eq!(result, Err(HouseError::DeviceNotFound("tv".to_string())));
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Cannot find device with name \"tv\""
+        );
+    }
+
+    #[test]
+    fn test_add_device_item() -> Result<(), HouseError> {
+        let mut room = Room::new("Kitchen", Vec::new());
+
+        room.add_device_item(Socket::new("kettle", "Hallway", false, 0.0))?;
+        let result = room.add_device_item(Thermometer::new("kettle", "Kitchen", true, 90.0));
 
-        room.remove_device("tv");
+        assert_eq!(
+            result,
+            Err(HouseError::TryingToAddAnExistingDevice(
+                "kettle".to_string()
+            ))
+        );
+        assert_eq!(room.device_names(), vec!["kettle".to_string()]);
+        assert_eq!(room.device("kettle").unwrap().parent_room(), "Kitchen");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_device() -> Result<(), HouseError> {
+        let mut room = Room::with_devices(
+            "Kitchen",
+            vec![Socket::new("kettle", "Kitchen", false, 0.0).into()],
+        )?;
+        room.add_device("tv".to_string())?;
+
+        assert!(room.get_device::<Socket>("kettle").is_some());
+        assert!(room.get_device::<Thermometer>("kettle").is_none());
+        assert!(room.device("tv").is_none());
+
+        room.get_device_mut::<Socket>("kettle").unwrap().status = true;
+
+        assert!(room.get_device::<Socket>("kettle").unwrap().status);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_owned_device() -> Result<(), HouseError> {
+        let mut room = Room::new("Kitchen", Vec::new());
+
+        room.add_device_item(Socket::new("kettle", "Kitchen", false, 0.0))?;
+        let device = room.take_device("kettle")?;
+
+        assert_eq!(device.unwrap().get_name(), "kettle");
+        assert!(room.device_names().is_empty());
+        assert_eq!(room.devices().count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_owned_device() -> Result<(), HouseError> {
+        let mut room = Room::with_devices(
+            "Kitchen",
+            vec![Socket::new("kettle", "Kitchen", false, 0.0).into()],
+        )?;
+        room.add_device("tv".to_string())?;
+
+        room.get_device_mut::<Socket>("kettle").unwrap().name = "teapot".to_string();
+
+        assert_eq!(
+            room.device_names(),
+            vec!["teapot".to_string(), "tv".to_string()]
+        );
+        assert!(room.get_device::<Socket>("teapot").is_some());
+        assert!(!room.is_device_exist("kettle"));
+        assert_eq!(room.take_device("teapot")?.unwrap().get_name(), "teapot");
 
-        assert_eq!(room.device_names.len(), 0);
+        Ok(())
     }
 }
@@ -1,4 +1,4 @@
-pub fn are_vecs_equal<T: PartialEq>(vec1: &Vec<T>, vec2: &Vec<T>) -> bool {
+pub fn are_vecs_equal<T: PartialEq>(vec1: &[T], vec2: &[T]) -> bool {
     if vec1.len() != vec2.len() {
         return false;
     }
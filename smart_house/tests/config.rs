@@ -0,0 +1,38 @@
+use smart_house::{
+    config::ConfigError,
+    devices::{socket::Socket, thermometer::Thermometer},
+    house::House,
+    room::Room,
+};
+
+#[test]
+fn test_save_and_load() -> Result<(), Box<dyn std::error::Error>> {
+    let mut house = House::new("My home");
+    house.add_room(Room::with_devices(
+        "Living room",
+        vec![
+            Socket::new("my socket", "Living room", false, 2.0).into(),
+            Thermometer::new("thermometer", "Living room", true, 21.5).into(),
+        ],
+    )?)?;
+
+    for extension in ["json", "toml", "yaml"] {
+        let path =
+            std::env::temp_dir().join(format!("smart-house-{}.{}", std::process::id(), extension));
+
+        house.save(&path)?;
+        let loaded = House::load(&path);
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(loaded?, house);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_load_missing_file() {
+    let result = House::load("missing-house.toml");
+
+    assert!(matches!(result, Err(ConfigError::IoError(_))));
+}
@@ -27,10 +27,10 @@ impl DeviceInfoProvider for OwningDeviceInfoProvider {
 fn test_create_report() {
     let mut house = House::new("My home [owning]");
 
-    let room = Room {
-        name: "Living room".to_string(),
-        device_names: vec!["my socket".to_string(), "non-existent device".to_string()],
-    };
+    let room = Room::new(
+        "Living room",
+        vec!["my socket".to_string(), "non-existent device".to_string()],
+    );
 
     let _r1 = house.add_room(room);
 
@@ -45,3 +45,22 @@ fn test_create_report() {
 
     assert_eq!(report, expected_report);
 }
+
+#[test]
+fn test_create_report_of_owned_devices() {
+    let mut house = House::new("My home");
+
+    let mut room = Room::new("Living room", Vec::new());
+    let _r1 = room.add_device_item(Socket::new("my socket", "Living room", false, 2.0));
+    let _r2 = room.add_device("non-existent device".to_string());
+
+    let _r3 = house.add_room(room);
+
+    let report = house.create_report(&house);
+
+    let expected_report =
+        "House: My home\nLiving room:\nmy socket is Off. Power consumption is 2\n\
+        non-existent device: Cannot find device with name \"non-existent device\"";
+
+    assert_eq!(report, expected_report);
+}
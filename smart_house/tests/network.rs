@@ -0,0 +1,311 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use smart_house::{
+    devices::{
+        device::{AsyncDeviceInfoProvider, DeviceConnectionError, DeviceInfoProvider},
+        network::NetworkDeviceProvider,
+    },
+    house::House,
+    room::Room,
+};
+use socket_tcp::{
+    client::Client,
+    command::Command,
+    protocol::{self, PROTOCOL_VERSION},
+    registry::SocketRegistry,
+    response::{Power, Response, SocketStatus},
+    server,
+    socket::Socket,
+};
+use thermometer_udp::{sender::Sender, thermometer::ThermometerConfig};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+use tokio::sync::Notify;
+
+fn start_server(runtime: &Runtime, address: &str) {
+    let address = address.to_string();
+    let started = Arc::new(Notify::new());
+    let notify_started = started.clone();
+
+    let registry = SocketRegistry::new(vec![Socket::new("kettle", 2000.0)]).unwrap();
+
+    runtime.spawn(async move {
+        server::run_server(&address, registry, || notify_started.notify_one())
+            .await
+            .unwrap();
+    });
+
+    runtime.block_on(started.notified());
+}
+
+// Socket server with a kettle that is on and uses 20 W, the reply to the
+// first command it gets is late by `delay`. Connections are served
+// concurrently, so a client that gave up can reconnect right away.
+async fn run_slow_server(listener: TcpListener, delay: Duration) {
+    let first = Arc::new(AtomicBool::new(true));
+
+    loop {
+        let (stream, _) = listener.accept().await.unwrap();
+        let first = first.clone();
+
+        tokio::spawn(async move {
+            let mut stream = BufReader::new(stream);
+
+            let mut handshake = String::new();
+            stream.read_line(&mut handshake).await.unwrap();
+            stream
+                .write_all(protocol::handshake_line(PROTOCOL_VERSION).as_bytes())
+                .await
+                .unwrap();
+
+            while let Ok(Some(payload)) = protocol::read_frame(&mut stream).await {
+                let name = "kettle".to_string();
+                let response = match String::from_utf8(payload).unwrap().parse() {
+                    Ok(Command::GetPowerConsumption(_)) => Response::PowerConsumption {
+                        name,
+                        power: Power::watts(20.0),
+                    },
+                    _ => Response::Status {
+                        name,
+                        status: SocketStatus::On,
+                    },
+                };
+
+                if first.swap(false, Ordering::SeqCst) {
+                    tokio::time::sleep(delay).await;
+                }
+
+                if protocol::write_frame(&mut stream, response.encode().as_bytes())
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[test]
+fn test_report_of_live_devices() {
+    let runtime = Runtime::new().unwrap();
+    start_server(&runtime, "127.0.0.1:3370");
+
+    let mut provider = NetworkDeviceProvider::new()
+        .unwrap()
+        .with_timeout(Duration::from_millis(500));
+
+    provider.add_socket("Kitchen", "kettle", Client::new("127.0.0.1:3370"), "kettle");
+    provider.add_socket(
+        "Kitchen",
+        "toaster",
+        Client::new("127.0.0.1:3370"),
+        "toaster",
+    );
+    provider
+        .add_thermometer_with_config(
+            "Kitchen",
+            "thermometer",
+            "127.0.0.1:3371",
+            ThermometerConfig {
+                jitter: 0.0,
+                ..ThermometerConfig::default()
+            },
+        )
+        .unwrap();
+
+    runtime.block_on(async {
+        Client::new("127.0.0.1:3370")
+            .turn_on("kettle")
+            .await
+            .unwrap();
+
+        let mut sender = Sender::new("127.0.0.1:3371", 1).await.unwrap();
+        sender.send(21.5).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    });
+
+    let mut house = House::new("My house");
+    let _r = house.add_room(Room::new(
+        "Kitchen",
+        vec![
+            "kettle".to_string(),
+            "thermometer".to_string(),
+            "toaster".to_string(),
+            "radio".to_string(),
+        ],
+    ));
+
+    let report = house.create_report_lines(&provider);
+
+    assert_eq!(report.len(), 6);
+    assert!(report[2].starts_with("kettle is On. Power consumption is "));
+    assert_eq!(report[3], "thermometer temperature is 21.5°C");
+    assert_eq!(
+        report[4],
+        "toaster: Cannot find device with name \"toaster\""
+    );
+    assert_eq!(report[5], "radio: Cannot find device with name \"radio\"");
+}
+
+#[test]
+fn test_unresponsive_devices_time_out() {
+    // Accepts connections into the backlog but never answers
+    let _listener = std::net::TcpListener::bind("127.0.0.1:3372").unwrap();
+
+    let mut provider = NetworkDeviceProvider::new()
+        .unwrap()
+        .with_timeout(Duration::from_millis(200));
+
+    provider.add_socket("Kitchen", "kettle", Client::new("127.0.0.1:3372"), "kettle");
+    provider.add_socket("Kitchen", "lamp", Client::new("127.0.0.1:3373"), "lamp");
+    provider
+        .add_thermometer_with_config(
+            "Kitchen",
+            "thermometer",
+            "127.0.0.1:3374",
+            ThermometerConfig {
+                stale_after: Duration::from_millis(50),
+                ..ThermometerConfig::default()
+            },
+        )
+        .unwrap();
+
+    std::thread::sleep(Duration::from_millis(100));
+
+    assert!(matches!(
+        provider.get_device_info("Kitchen", "kettle"),
+        Err(DeviceConnectionError::TimedOutError)
+    ));
+    assert!(matches!(
+        provider.get_device_info("Kitchen", "thermometer"),
+        Err(DeviceConnectionError::TimedOutError)
+    ));
+    assert!(matches!(
+        provider.get_device_info("Kitchen", "lamp"),
+        Err(DeviceConnectionError::ConnectionError(_))
+    ));
+    assert_eq!(
+        provider.get_info("Kitchen", "kettle"),
+        "kettle: Connection timed out"
+    );
+}
+
+#[tokio::test]
+async fn test_async_report_with_deadline() {
+    let started = Arc::new(Notify::new());
+    let notify_started = started.clone();
+    let registry = SocketRegistry::new(vec![Socket::new("kettle", 2000.0)]).unwrap();
+
+    tokio::spawn(async move {
+        server::run_server("127.0.0.1:3375", registry, || notify_started.notify_one())
+            .await
+            .unwrap();
+    });
+    started.notified().await;
+
+    let _listener = tokio::net::TcpListener::bind("127.0.0.1:3377")
+        .await
+        .unwrap();
+
+    let mut provider = NetworkDeviceProvider::new().unwrap();
+
+    provider.add_socket("Kitchen", "kettle", Client::new("127.0.0.1:3375"), "kettle");
+    provider.add_socket("Kitchen", "lamp", Client::new("127.0.0.1:3377"), "lamp");
+    provider
+        .add_thermometer_async(
+            "Kitchen",
+            "thermometer",
+            "127.0.0.1:3376",
+            ThermometerConfig {
+                jitter: 0.0,
+                ..ThermometerConfig::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let mut sender = Sender::new("127.0.0.1:3376", 1).await.unwrap();
+    sender.send(19.0).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(
+        provider.fetch_info("Kitchen", "thermometer").await.unwrap(),
+        "thermometer temperature is 19°C"
+    );
+
+    // Blocking calls refuse to run inside the test's runtime instead of panicking
+    assert!(matches!(
+        provider.get_device_info("Kitchen", "thermometer"),
+        Err(DeviceConnectionError::ConnectionError(_))
+    ));
+    assert!(provider
+        .add_thermometer("Kitchen", "other thermometer", "127.0.0.1:0")
+        .is_err());
+
+    let mut house = House::new("My house");
+    let _r = house.add_room(Room::new(
+        "Kitchen",
+        vec![
+            "lamp".to_string(),
+            "kettle".to_string(),
+            "thermometer".to_string(),
+        ],
+    ));
+
+    let report = house
+        .create_report_lines_async(&provider, Duration::from_millis(300))
+        .await;
+
+    assert_eq!(report[2], "lamp: unavailable");
+    assert!(report[3].starts_with("kettle is Off. Power consumption is "));
+    assert_eq!(report[4], "thermometer temperature is 19°C");
+}
+
+#[test]
+fn test_query_after_timeout() {
+    let runtime = Runtime::new().unwrap();
+    let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    runtime.spawn(run_slow_server(listener, Duration::from_millis(400)));
+
+    let mut provider = NetworkDeviceProvider::new()
+        .unwrap()
+        .with_timeout(Duration::from_millis(200));
+    provider.add_socket("Kitchen", "kettle", Client::new(&address), "kettle");
+
+    assert!(matches!(
+        provider.get_device_info("Kitchen", "kettle"),
+        Err(DeviceConnectionError::TimedOutError)
+    ));
+    assert_eq!(
+        provider.get_device_info("Kitchen", "kettle").unwrap(),
+        "kettle is On. Power consumption is 20"
+    );
+}
+
+#[tokio::test]
+async fn test_async_reports_after_late_device() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    tokio::spawn(run_slow_server(listener, Duration::from_millis(400)));
+
+    let mut provider = NetworkDeviceProvider::new().unwrap();
+    provider.add_socket("Kitchen", "kettle", Client::new(&address), "kettle");
+
+    let mut house = House::new("My house");
+    let _r = house.add_room(Room::new("Kitchen", vec!["kettle".to_string()]));
+
+    let first = house
+        .create_report_lines_async(&provider, Duration::from_millis(200))
+        .await;
+    let second = house
+        .create_report_lines_async(&provider, Duration::from_millis(200))
+        .await;
+
+    assert_eq!(first[2], "kettle: unavailable");
+    assert_eq!(second[2], "kettle is On. Power consumption is 20");
+}
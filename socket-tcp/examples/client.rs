@@ -2,14 +2,20 @@ use socket_tcp::client::{Client, ConnectionError};
 
 #[tokio::main]
 async fn main() -> Result<(), ConnectionError> {
-    let socket_client = Client {
-        address: "127.0.0.1:3333".to_string(),
-    };
-
-    println!("{:?}", socket_client.turn_on().await?);
-    println!("{:?}", socket_client.get_status().await?);
-    println!("{:?}", socket_client.turn_off().await?);
-    println!("{:?}", socket_client.get_status().await?);
+    let socket_client = Client::new("127.0.0.1:3333");
+
+    println!("{:?}", socket_client.list_sockets().await?);
+    socket_client.add_socket("kettle", 2000.0).await?;
+
+    println!("{:?}", socket_client.turn_on("my socket").await?);
+    println!("{:?}", socket_client.get_status("my socket").await?);
+    println!("{:?}", socket_client.turn_off("my socket").await?);
+    println!("{:?}", socket_client.get_status("my socket").await?);
+
+    println!("{:?}", socket_client.turn_on("kettle").await?);
+    println!("{}", socket_client.get_power_consumption("kettle").await?);
+    println!("{} kWh", socket_client.get_energy("kettle").await?);
+    socket_client.remove_socket("kettle").await?;
 
     Ok(())
 }
@@ -1,10 +1,17 @@
-use socket_tcp::server;
+use socket_tcp::{registry::SocketRegistry, server, socket::Socket};
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     let address = "127.0.0.1:3333";
 
-    server::run_server(address, || {
+    let registry = SocketRegistry::new(vec![
+        Socket::new("my socket", 20.0),
+        Socket::new("lamp", 60.0),
+        Socket::new("fridge", 150.0),
+    ])
+    .expect("Socket names should be unique");
+
+    server::run_server(address, registry, || {
         println!("Server is listening on {}", &address);
     })
     .await?;
@@ -0,0 +1,14 @@
+use futures::StreamExt;
+use socket_tcp::client::{Client, ConnectionError};
+
+#[tokio::main]
+async fn main() -> Result<(), ConnectionError> {
+    let socket_client = Client::new("127.0.0.1:3333");
+    let mut events = socket_client.subscribe(None).await?;
+
+    while let Some(event) = events.next().await {
+        println!("{}", event?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const NONCE_SIZE: usize = 16;
+
+/// What an authenticated client may do. `Control` includes everything
+/// `ReadOnly` allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    ReadOnly,
+    Control,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::ReadOnly => write!(f, "read"),
+            Role::Control => write!(f, "control"),
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "read" => Ok(Role::ReadOnly),
+            "control" => Ok(Role::Control),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Secret a client proves it knows by signing the server challenge
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub client_id: String,
+    secret: Vec<u8>,
+}
+
+impl Credentials {
+    pub fn new(client_id: &str, secret: &[u8]) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            secret: secret.to_vec(),
+        }
+    }
+
+    pub fn sign(&self, nonce: &str) -> String {
+        sign(&self.secret, nonce)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ClientKey {
+    secret: Vec<u8>,
+    role: Role,
+}
+
+/// Pre-shared secrets of the clients a server accepts
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    clients: HashMap<String, ClientKey>,
+    /// Role of clients that don't authenticate, `None` rejects their commands
+    pub anonymous_role: Option<Role>,
+}
+
+impl AuthConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_client(mut self, client_id: &str, secret: &[u8], role: Role) -> Self {
+        self.clients.insert(
+            client_id.to_string(),
+            ClientKey {
+                secret: secret.to_vec(),
+                role,
+            },
+        );
+        self
+    }
+
+    pub fn with_anonymous_role(mut self, role: Role) -> Self {
+        self.anonymous_role = Some(role);
+        self
+    }
+
+    /// Role of `client_id` if `signature` is its signature of `nonce`
+    pub fn verify(&self, client_id: &str, nonce: &str, signature: &str) -> Option<Role> {
+        let key = self.clients.get(client_id)?;
+        let signature = hex::decode(signature).ok()?;
+
+        let mut mac = HmacSha256::new_from_slice(&key.secret).ok()?;
+        mac.update(nonce.as_bytes());
+        mac.verify_slice(&signature).ok()?;
+
+        Some(key.role)
+    }
+}
+
+/// Hex encoded HMAC-SHA256 of `nonce`
+pub fn sign(secret: &[u8], nonce: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(nonce.as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub fn new_nonce() -> String {
+    hex::encode(rand::thread_rng().gen::<[u8; NONCE_SIZE]>())
+}
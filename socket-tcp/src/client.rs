@@ -1,14 +1,45 @@
-use std::str::from_utf8;
+use std::io;
+use std::time::Duration;
 
+use futures::stream::{self, BoxStream, StreamExt};
 use thiserror::Error;
-use tokio::io::AsyncReadExt;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
 
+use crate::auth::Credentials;
 use crate::command::Command;
+use crate::event::Event;
+use crate::protocol::{self, Protocol, AUTH_ACCEPTED, PROTOCOL_VERSION};
+use crate::response::{ErrorCode, Power, Response, SocketStatus};
+use crate::schedule::{Schedule, ScheduleAction, Trigger};
+
+/// Time the server has to answer the handshake and the authentication
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct Client {
     pub address: String,
+
+    credentials: Option<Credentials>,
+    connection: Mutex<Option<Connection>>,
+}
+
+/// Events pushed by the server, ends when the server closes the subscription
+pub type EventStream = BoxStream<'static, Result<Event, ConnectionError>>;
+
+enum Connection {
+    Framed(BufReader<TcpStream>, u8),
+    Text,
+}
+
+impl Connection {
+    fn protocol(&self) -> Protocol {
+        match self {
+            Connection::Framed(_, version) => Protocol::Framed(*version),
+            Connection::Text => Protocol::Text,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -17,51 +48,446 @@ pub enum ConnectionError {
     CantConnect(String),
     #[error("Failed to read from connection")]
     CantRead,
+    #[error("Failed to write to connection")]
+    CantWrite,
+    #[error("Received malformed response")]
+    MalformedResponse,
+    #[error("Server responded with error {:?}: {}", .0, .1)]
+    ServerError(ErrorCode, String),
+    #[error("Server does not support subscriptions")]
+    SubscriptionsUnsupported,
+    #[error("Not authorized by the server")]
+    Unauthorized,
 }
 
 impl Client {
-    async fn send_command(&self, command: Command) -> Result<String, ConnectionError> {
-        let command_str = match command {
-            Command::GetStatus => "get_status",
-            Command::TurnOn => "turn_on",
-            Command::TurnOff => "turn_off",
-            Command::GetPowerConsumption => "get_power_consumption",
-        };
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            credentials: None,
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Answers the server challenge with `credentials`, otherwise the client
+    /// gets whatever the server allows anonymous clients
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
 
-        match TcpStream::connect(&self.address).await {
-            Ok(mut stream) => {
-                let msg = format!("{}\n", command_str);
+    /// Protocol negotiated with the server, connects if not connected yet
+    pub async fn protocol(&self) -> Result<Protocol, ConnectionError> {
+        let mut connection = self.connection.lock().await;
 
-                stream.write_all(msg.as_bytes()).await.unwrap();
+        if connection.is_none() {
+            *connection = Some(self.connect().await?);
+        }
 
-                let mut data = [0; 64];
-                match stream.read(&mut data).await {
-                    Ok(size) => {
-                        let subslice = data.get(0..size).unwrap();
-                        let result = from_utf8(subslice).unwrap().to_owned();
+        Ok(connection
+            .as_ref()
+            .map_or(Protocol::Text, Connection::protocol))
+    }
 
-                        Ok(result)
-                    }
-                    _ => Err(ConnectionError::CantRead),
-                }
+    async fn open_stream(&self) -> Result<TcpStream, ConnectionError> {
+        TcpStream::connect(&self.address)
+            .await
+            .map_err(|e| ConnectionError::CantConnect(e.to_string()))
+    }
+
+    async fn connect(&self) -> Result<Connection, ConnectionError> {
+        let mut stream = BufReader::new(self.open_stream().await?);
+
+        stream
+            .write_all(protocol::handshake_line(PROTOCOL_VERSION).as_bytes())
+            .await
+            .map_err(|_| ConnectionError::CantWrite)?;
+
+        // A legacy server drops the connection on the handshake line
+        let mut reply = String::new();
+        let version = match timeout(HANDSHAKE_TIMEOUT, stream.read_line(&mut reply)).await {
+            Ok(Ok(_)) => protocol::parse_handshake(&reply).unwrap_or(0),
+            Ok(Err(_)) => 0,
+            Err(_) => return Err(ConnectionError::CantRead),
+        };
+
+        if let Some(nonce) = protocol::parse_challenge(&reply) {
+            self.authenticate(&mut stream, nonce).await?;
+        }
+
+        match Protocol::from_version(version) {
+            Protocol::Framed(version) => Ok(Connection::Framed(stream, version)),
+            Protocol::Text => Ok(Connection::Text),
+        }
+    }
+
+    async fn authenticate(
+        &self,
+        stream: &mut BufReader<TcpStream>,
+        nonce: &str,
+    ) -> Result<(), ConnectionError> {
+        let line = match &self.credentials {
+            Some(credentials) => {
+                protocol::auth_line(Some((&credentials.client_id, &credentials.sign(nonce))))
             }
-            Err(e) => Err(ConnectionError::CantConnect(e.to_string())),
+            None => protocol::auth_line(None),
+        };
+
+        stream
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|_| ConnectionError::CantWrite)?;
+
+        let mut reply = String::new();
+        timeout(HANDSHAKE_TIMEOUT, stream.read_line(&mut reply))
+            .await
+            .map_err(|_| ConnectionError::CantRead)?
+            .map_err(|_| ConnectionError::CantRead)?;
+
+        match reply.split_whitespace().next() {
+            Some(AUTH_ACCEPTED) => Ok(()),
+            _ => Err(ConnectionError::Unauthorized),
         }
     }
 
-    pub async fn get_status(&self) -> Result<String, ConnectionError> {
-        Self::send_command(self, Command::GetStatus).await
+    async fn send_command(&self, command: Command) -> Result<Response, ConnectionError> {
+        let mut connection = self.connection.lock().await;
+
+        // Taken until the whole response is read, so a command cancelled
+        // halfway doesn't leave its reply for the next one
+        let (mut current, reused) = match connection.take() {
+            Some(current) => (current, true),
+            None => (self.connect().await?, false),
+        };
+
+        let mut reply = self.send(&mut current, &command).await?;
+
+        // The server closes idle connections, so a reused one may be gone
+        // before the command got there
+        if reply.is_none() && reused {
+            current = self.connect().await?;
+            reply = self.send(&mut current, &command).await?;
+        }
+
+        let text = reply.ok_or(ConnectionError::CantRead)?;
+        let response = decode_response(current.protocol(), &command, &text)?;
+
+        // Broken streams aren't put back, the next command reconnects
+        *connection = Some(current);
+
+        match response {
+            Response::Error { code, message } => Err(server_error(code, message)),
+            response => Ok(response),
+        }
+    }
+
+    /// The reply to the command, `None` if the connection was closed before
+    /// any of it arrived
+    async fn send(
+        &self,
+        connection: &mut Connection,
+        command: &Command,
+    ) -> Result<Option<String>, ConnectionError> {
+        match connection {
+            Connection::Framed(stream, _) => Self::send_framed(stream, command).await,
+            Connection::Text => self.send_text(command).await.map(Some),
+        }
+    }
+
+    async fn send_framed(
+        stream: &mut BufReader<TcpStream>,
+        command: &Command,
+    ) -> Result<Option<String>, ConnectionError> {
+        match protocol::write_frame(stream, command.to_string().as_bytes()).await {
+            Ok(()) => {}
+            Err(e) if is_closed(&e) => return Ok(None),
+            Err(_) => return Err(ConnectionError::CantWrite),
+        }
+
+        match stream.fill_buf().await {
+            Ok([]) => return Ok(None),
+            Ok(_) => {}
+            Err(e) if is_closed(&e) => return Ok(None),
+            Err(_) => return Err(ConnectionError::CantRead),
+        }
+
+        match protocol::read_frame(stream).await {
+            Ok(Some(payload)) => String::from_utf8(payload)
+                .map(Some)
+                .map_err(|_| ConnectionError::MalformedResponse),
+            _ => Err(ConnectionError::CantRead),
+        }
+    }
+
+    async fn send_text(&self, command: &Command) -> Result<String, ConnectionError> {
+        let mut stream = self.open_stream().await?;
+        let msg = format!("{}\n", command);
+
+        stream
+            .write_all(msg.as_bytes())
+            .await
+            .map_err(|_| ConnectionError::CantWrite)?;
+
+        let mut data = Vec::new();
+        stream
+            .read_to_end(&mut data)
+            .await
+            .map_err(|_| ConnectionError::CantRead)?;
+
+        String::from_utf8(data).map_err(|_| ConnectionError::MalformedResponse)
+    }
+
+    async fn send_status_command(&self, command: Command) -> Result<SocketStatus, ConnectionError> {
+        match Self::send_command(self, command).await? {
+            Response::Status { status, .. } => Ok(status),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    pub async fn get_status(&self, socket_name: &str) -> Result<SocketStatus, ConnectionError> {
+        Self::send_status_command(self, Command::GetStatus(socket_name.to_string())).await
+    }
+
+    pub async fn turn_on(&self, socket_name: &str) -> Result<SocketStatus, ConnectionError> {
+        Self::send_status_command(self, Command::TurnOn(socket_name.to_string())).await
     }
 
-    pub async fn turn_on(&self) -> Result<String, ConnectionError> {
-        Self::send_command(self, Command::TurnOn).await
+    pub async fn turn_off(&self, socket_name: &str) -> Result<SocketStatus, ConnectionError> {
+        Self::send_status_command(self, Command::TurnOff(socket_name.to_string())).await
     }
 
-    pub async fn turn_off(&self) -> Result<String, ConnectionError> {
-        Self::send_command(self, Command::TurnOff).await
+    pub async fn get_power_consumption(&self, socket_name: &str) -> Result<Power, ConnectionError> {
+        let command = Command::GetPowerConsumption(socket_name.to_string());
+
+        match Self::send_command(self, command).await? {
+            Response::PowerConsumption { power, .. } => Ok(power),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
     }
 
-    pub async fn get_power_consumption(&self) -> Result<String, ConnectionError> {
-        Self::send_command(self, Command::GetPowerConsumption).await
+    /// Energy consumed since the socket was added or its meter was reset
+    pub async fn get_energy(&self, socket_name: &str) -> Result<f64, ConnectionError> {
+        match Self::send_command(self, Command::GetEnergy(socket_name.to_string())).await? {
+            Response::Energy { energy_kwh, .. } => Ok(energy_kwh),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    pub async fn reset_energy(&self, socket_name: &str) -> Result<(), ConnectionError> {
+        match Self::send_command(self, Command::ResetEnergy(socket_name.to_string())).await? {
+            Response::EnergyReset(_) => Ok(()),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    pub async fn add_schedule(
+        &self,
+        socket_name: &str,
+        action: ScheduleAction,
+        trigger: Trigger,
+    ) -> Result<Schedule, ConnectionError> {
+        let command = Command::AddSchedule {
+            name: socket_name.to_string(),
+            action,
+            trigger,
+        };
+
+        match Self::send_command(self, command).await? {
+            Response::ScheduleAdded(schedule) => Ok(schedule),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    pub async fn list_schedules(
+        &self,
+        socket_name: &str,
+    ) -> Result<Vec<Schedule>, ConnectionError> {
+        match Self::send_command(self, Command::ListSchedules(socket_name.to_string())).await? {
+            Response::Schedules(schedules) => Ok(schedules),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    pub async fn cancel_schedule(&self, socket_name: &str, id: u64) -> Result<(), ConnectionError> {
+        let command = Command::CancelSchedule {
+            name: socket_name.to_string(),
+            id,
+        };
+
+        match Self::send_command(self, command).await? {
+            Response::ScheduleCancelled(_) => Ok(()),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    /// Opens a dedicated connection that receives events of `socket_name`,
+    /// or of every socket when `None`
+    pub async fn subscribe(
+        &self,
+        socket_name: Option<&str>,
+    ) -> Result<EventStream, ConnectionError> {
+        let (mut stream, protocol) = match self.connect().await? {
+            Connection::Framed(stream, version) => (stream, Protocol::Framed(version)),
+            Connection::Text => return Err(ConnectionError::SubscriptionsUnsupported),
+        };
+
+        if !protocol.has_typed_responses() {
+            return Err(ConnectionError::SubscriptionsUnsupported);
+        }
+
+        let command = Command::Subscribe(socket_name.map(|name| name.to_string()));
+        let text = Self::send_framed(&mut stream, &command)
+            .await?
+            .ok_or(ConnectionError::CantRead)?;
+
+        match decode_response(protocol, &command, &text)? {
+            Response::Subscribed => {}
+            Response::Error { code, message } => return Err(server_error(code, message)),
+            _ => return Err(ConnectionError::MalformedResponse),
+        }
+
+        // The stream ends after the first error
+        let events = stream::unfold(Some(stream), |stream| async move {
+            let mut stream = stream?;
+
+            let event = match protocol::read_frame(&mut stream).await {
+                Ok(Some(payload)) => match String::from_utf8(payload)
+                    .ok()
+                    .as_deref()
+                    .and_then(Response::decode)
+                {
+                    Some(Response::Event(event)) => Ok(event),
+                    _ => Err(ConnectionError::MalformedResponse),
+                },
+                Ok(None) => return None,
+                Err(_) => Err(ConnectionError::CantRead),
+            };
+
+            let stream = event.is_ok().then_some(stream);
+
+            Some((event, stream))
+        });
+
+        Ok(events.boxed())
+    }
+
+    pub async fn list_sockets(&self) -> Result<Vec<String>, ConnectionError> {
+        match Self::send_command(self, Command::ListSockets).await? {
+            Response::Sockets(names) => Ok(names),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    pub async fn add_socket(
+        &self,
+        socket_name: &str,
+        power_consumption: f32,
+    ) -> Result<(), ConnectionError> {
+        let command = Command::AddSocket {
+            name: socket_name.to_string(),
+            power_consumption,
+        };
+
+        match Self::send_command(self, command).await? {
+            Response::Added(_) => Ok(()),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    /// Subscribers are notified when the socket power crosses `watts`, `None`
+    /// removes the threshold
+    pub async fn set_power_threshold(
+        &self,
+        socket_name: &str,
+        watts: Option<f32>,
+    ) -> Result<(), ConnectionError> {
+        let command = Command::SetPowerThreshold {
+            name: socket_name.to_string(),
+            watts,
+        };
+
+        match Self::send_command(self, command).await? {
+            Response::PowerThresholdSet { .. } => Ok(()),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+
+    pub async fn remove_socket(&self, socket_name: &str) -> Result<(), ConnectionError> {
+        match Self::send_command(self, Command::RemoveSocket(socket_name.to_string())).await? {
+            Response::Removed(_) => Ok(()),
+            _ => Err(ConnectionError::MalformedResponse),
+        }
+    }
+}
+
+fn is_closed(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
+
+fn server_error(code: ErrorCode, message: String) -> ConnectionError {
+    match code {
+        ErrorCode::Unauthorized => ConnectionError::Unauthorized,
+        code => ConnectionError::ServerError(code, message),
+    }
+}
+
+fn decode_response(
+    protocol: Protocol,
+    command: &Command,
+    text: &str,
+) -> Result<Response, ConnectionError> {
+    let response = match protocol.has_typed_responses() {
+        true => Response::decode(text),
+        false => decode_legacy_response(command, text),
+    };
+
+    response.ok_or(ConnectionError::MalformedResponse)
+}
+
+/// Older servers reply with human readable text, so the value is recovered
+/// from the reply to the command that was sent. Replies that don't fit are
+/// taken for errors if their wording is known.
+fn decode_legacy_response(command: &Command, text: &str) -> Option<Response> {
+    decode_legacy_reply(command, text).or_else(|| {
+        Some(Response::Error {
+            code: ErrorCode::from_message(text)?,
+            message: text.to_string(),
+        })
+    })
+}
+
+fn decode_legacy_reply(command: &Command, text: &str) -> Option<Response> {
+    match command {
+        Command::GetStatus(name) | Command::TurnOn(name) | Command::TurnOff(name) => {
+            let (_, status) = text.rsplit_once(" is ")?;
+
+            Some(Response::Status {
+                name: name.to_string(),
+                status: status.parse().ok()?,
+            })
+        }
+        Command::GetPowerConsumption(name) => {
+            let value = text.strip_prefix("Power consumption is ")?;
+
+            Some(Response::PowerConsumption {
+                name: name.to_string(),
+                power: Power::watts(value.parse().ok()?),
+            })
+        }
+        Command::ListSockets => Some(Response::Sockets(
+            text.lines().map(|name| name.to_string()).collect(),
+        )),
+        Command::AddSocket { name, .. } if text == format!("{} added", name) => {
+            Some(Response::Added(name.to_string()))
+        }
+        Command::RemoveSocket(name) if text == format!("{} removed", name) => {
+            Some(Response::Removed(name.to_string()))
+        }
+        _ => None,
     }
 }
@@ -1,22 +1,175 @@
+use std::fmt;
 use std::str::FromStr;
 
+use crate::auth::Role;
+use crate::schedule::{ScheduleAction, Trigger};
+
+/// Socket addressed by commands sent without a socket name (legacy clients)
+pub const DEFAULT_SOCKET_NAME: &str = "my socket";
+
 pub enum Command {
-    GetStatus,
-    GetPowerConsumption,
-    TurnOn,
-    TurnOff,
+    GetStatus(String),
+    GetPowerConsumption(String),
+    TurnOn(String),
+    TurnOff(String),
+    GetEnergy(String),
+    ResetEnergy(String),
+    ListSockets,
+    AddSocket {
+        name: String,
+        power_consumption: f32,
+    },
+    RemoveSocket(String),
+    /// Subscribers are notified when the power crosses `watts`, `None`
+    /// removes the threshold
+    SetPowerThreshold {
+        name: String,
+        watts: Option<f32>,
+    },
+    AddSchedule {
+        name: String,
+        action: ScheduleAction,
+        trigger: Trigger,
+    },
+    ListSchedules(String),
+    CancelSchedule {
+        name: String,
+        id: u64,
+    },
+    /// Keeps the connection open and pushes events of one socket, or of all
+    /// sockets when no name is given
+    Subscribe(Option<String>),
+}
+
+impl Command {
+    /// Least role a client needs to run the command
+    pub fn required_role(&self) -> Role {
+        match self {
+            Command::GetStatus(_)
+            | Command::GetPowerConsumption(_)
+            | Command::GetEnergy(_)
+            | Command::ListSockets
+            | Command::ListSchedules(_)
+            | Command::Subscribe(_) => Role::ReadOnly,
+            _ => Role::Control,
+        }
+    }
 }
 
 impl FromStr for Command {
     type Err = ();
 
+    /// Names with tabs are rejected, typed responses separate fields with them
     fn from_str(input: &str) -> Result<Command, Self::Err> {
-        match input {
-            "get_status" => Ok(Command::GetStatus),
-            "get_power_consumption" => Ok(Command::GetPowerConsumption),
-            "turn_on" => Ok(Command::TurnOn),
-            "turn_off" => Ok(Command::TurnOff),
+        if input.contains('\t') {
+            return Err(());
+        }
+
+        let (command, args) = match input.split_once(' ') {
+            Some((command, args)) => (command, args.trim()),
+            None => (input, ""),
+        };
+
+        let socket_name = match args {
+            "" => DEFAULT_SOCKET_NAME.to_string(),
+            name => name.to_string(),
+        };
+
+        match command {
+            "get_status" => Ok(Command::GetStatus(socket_name)),
+            "get_power_consumption" => Ok(Command::GetPowerConsumption(socket_name)),
+            "turn_on" => Ok(Command::TurnOn(socket_name)),
+            "turn_off" => Ok(Command::TurnOff(socket_name)),
+            "get_energy" => Ok(Command::GetEnergy(socket_name)),
+            "reset_energy" => Ok(Command::ResetEnergy(socket_name)),
+            "list_sockets" => Ok(Command::ListSockets),
+            "add_socket" => {
+                let (power_consumption, name) = args.split_once(' ').ok_or(())?;
+                let power_consumption = power_consumption.parse().map_err(|_| ())?;
+
+                Ok(Command::AddSocket {
+                    name: name.trim().to_string(),
+                    power_consumption,
+                })
+            }
+            "remove_socket" if !args.is_empty() => Ok(Command::RemoveSocket(socket_name)),
+            "set_power_threshold" => {
+                let (watts, name) = args.split_once(' ').ok_or(())?;
+
+                Ok(Command::SetPowerThreshold {
+                    name: name.trim().to_string(),
+                    watts: match watts {
+                        "none" => None,
+                        watts => Some(watts.parse().map_err(|_| ())?),
+                    },
+                })
+            }
+            "add_schedule" => {
+                let (action, args) = args.split_once(' ').ok_or(())?;
+                let (trigger, name) = args.split_once(' ').ok_or(())?;
+
+                Ok(Command::AddSchedule {
+                    name: name.trim().to_string(),
+                    action: action.parse()?,
+                    trigger: trigger.parse()?,
+                })
+            }
+            "list_schedules" => Ok(Command::ListSchedules(socket_name)),
+            "cancel_schedule" => {
+                let (id, name) = args.split_once(' ').ok_or(())?;
+
+                Ok(Command::CancelSchedule {
+                    name: name.trim().to_string(),
+                    id: id.parse().map_err(|_| ())?,
+                })
+            }
+            "subscribe" => Ok(Command::Subscribe(match args {
+                "" => None,
+                name => Some(name.to_string()),
+            })),
             _ => Err(()),
         }
     }
 }
+
+// The default socket is sent without a name, so legacy servers understand it
+fn write_socket_command(f: &mut fmt::Formatter, command: &str, name: &str) -> fmt::Result {
+    match name {
+        DEFAULT_SOCKET_NAME => write!(f, "{}", command),
+        name => write!(f, "{} {}", command, name),
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::GetStatus(name) => write_socket_command(f, "get_status", name),
+            Command::TurnOn(name) => write_socket_command(f, "turn_on", name),
+            Command::TurnOff(name) => write_socket_command(f, "turn_off", name),
+            Command::GetPowerConsumption(name) => {
+                write_socket_command(f, "get_power_consumption", name)
+            }
+            Command::GetEnergy(name) => write_socket_command(f, "get_energy", name),
+            Command::ResetEnergy(name) => write_socket_command(f, "reset_energy", name),
+            Command::ListSockets => write!(f, "list_sockets"),
+            Command::AddSocket {
+                name,
+                power_consumption,
+            } => write!(f, "add_socket {} {}", power_consumption, name),
+            Command::RemoveSocket(name) => write!(f, "remove_socket {}", name),
+            Command::SetPowerThreshold { name, watts } => match watts {
+                Some(watts) => write!(f, "set_power_threshold {} {}", watts, name),
+                None => write!(f, "set_power_threshold none {}", name),
+            },
+            Command::AddSchedule {
+                name,
+                action,
+                trigger,
+            } => write!(f, "add_schedule {} {} {}", action, trigger, name),
+            Command::ListSchedules(name) => write_socket_command(f, "list_schedules", name),
+            Command::CancelSchedule { name, id } => write!(f, "cancel_schedule {} {}", id, name),
+            Command::Subscribe(None) => write!(f, "subscribe"),
+            Command::Subscribe(Some(name)) => write!(f, "subscribe {}", name),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+
+use crate::response::Power;
+
+/// State change of a socket pushed to subscribers
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    TurnedOn(String),
+    TurnedOff(String),
+    /// Power went above (`above == true`) or back below the socket threshold
+    PowerThresholdCrossed {
+        name: String,
+        power: Power,
+        above: bool,
+    },
+}
+
+impl Event {
+    pub fn socket_name(&self) -> &str {
+        match self {
+            Event::TurnedOn(name) | Event::TurnedOff(name) => name,
+            Event::PowerThresholdCrossed { name, .. } => name,
+        }
+    }
+
+    pub(crate) fn fields(&self) -> Vec<String> {
+        match self {
+            Event::TurnedOn(name) => vec!["turned_on".to_string(), name.to_string()],
+            Event::TurnedOff(name) => vec!["turned_off".to_string(), name.to_string()],
+            Event::PowerThresholdCrossed { name, power, above } => vec![
+                "threshold".to_string(),
+                name.to_string(),
+                power.value.to_string(),
+                power.unit.to_string(),
+                match above {
+                    true => "above".to_string(),
+                    false => "below".to_string(),
+                },
+            ],
+        }
+    }
+
+    pub(crate) fn from_fields(fields: &[&str]) -> Option<Self> {
+        match fields {
+            ["turned_on", name] => Some(Event::TurnedOn(name.to_string())),
+            ["turned_off", name] => Some(Event::TurnedOff(name.to_string())),
+            ["threshold", name, value, unit, direction] => Some(Event::PowerThresholdCrossed {
+                name: name.to_string(),
+                power: Power {
+                    value: value.parse().ok()?,
+                    unit: unit.parse().ok()?,
+                },
+                above: match *direction {
+                    "above" => true,
+                    "below" => false,
+                    _ => return None,
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::TurnedOn(name) => write!(f, "{} turned on", name),
+            Event::TurnedOff(name) => write!(f, "{} turned off", name),
+            Event::PowerThresholdCrossed { name, power, above } => match above {
+                true => write!(f, "{} power rose above threshold: {}", name, power),
+                false => write!(f, "{} power fell below threshold: {}", name, power),
+            },
+        }
+    }
+}
@@ -1,4 +1,11 @@
+pub mod auth;
 pub mod client;
 pub mod command;
+pub mod event;
+pub mod meter;
+pub mod protocol;
+pub mod registry;
+pub mod response;
+pub mod schedule;
 pub mod server;
 pub mod socket;
@@ -0,0 +1,76 @@
+use std::time::Instant;
+
+use rand::Rng;
+
+const SECONDS_IN_HOUR: f64 = 3600.0;
+
+/// Power drawn by an appliance: nothing when off, `nominal` watts give or
+/// take `variation` (a fraction of the nominal load) when on. A variation
+/// that isn't a finite number is ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadModel {
+    pub nominal: f32,
+    pub variation: f32,
+}
+
+impl LoadModel {
+    pub fn sample<R: Rng>(&self, is_on: bool, rng: &mut R) -> f32 {
+        if !is_on {
+            return 0.0;
+        }
+
+        // Scaled after sampling, a range as wide as the variation could overflow
+        let deviation = match self.variation > 0.0 && self.variation.is_finite() {
+            true => self.variation * rng.gen_range(-1.0..1.0),
+            false => 0.0,
+        };
+
+        (self.nominal * (1.0 + deviation)).max(0.0)
+    }
+}
+
+/// Integrates a piecewise constant load over time
+#[derive(Debug, Clone)]
+pub struct EnergyMeter {
+    energy_kwh: f64,
+    load: f32,
+    last_update: Instant,
+}
+
+impl EnergyMeter {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            energy_kwh: 0.0,
+            load: 0.0,
+            last_update: now,
+        }
+    }
+
+    /// Accounts the current load up to `now`, then switches to `load` watts
+    pub fn update(&mut self, now: Instant, load: f32) {
+        self.energy_kwh = self.energy_kwh_at(now);
+        self.load = load;
+        self.last_update = now;
+    }
+
+    /// Energy with the current load accounted up to `now`, the meter itself
+    /// is left as is
+    pub fn energy_kwh_at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_update);
+
+        self.energy_kwh + self.load as f64 * elapsed.as_secs_f64() / SECONDS_IN_HOUR / 1000.0
+    }
+
+    pub fn load(&self) -> f32 {
+        self.load
+    }
+
+    pub fn energy_kwh(&self) -> f64 {
+        self.energy_kwh
+    }
+
+    pub fn reset(&mut self, now: Instant) {
+        self.energy_kwh = 0.0;
+        self.last_update = now;
+    }
+}
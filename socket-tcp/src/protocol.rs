@@ -0,0 +1,150 @@
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::response::Response;
+
+/// Highest protocol version spoken by this crate. Version `0` is the legacy
+/// newline-terminated text protocol, version `1` frames human readable
+/// replies, version `2` frames encoded `Response`s, version `3` lets the
+/// server challenge the client to authenticate.
+pub const PROTOCOL_VERSION: u8 = 3;
+
+pub const TYPED_RESPONSES_VERSION: u8 = 2;
+
+pub const AUTH_VERSION: u8 = 3;
+
+pub const AUTH_PREFIX: &str = "auth";
+
+pub const AUTH_ACCEPTED: &str = "ok";
+
+pub const HANDSHAKE_PREFIX: &str = "hello";
+
+/// Upper bound for a single frame payload, protects peers from huge allocations
+pub const MAX_FRAME_SIZE: u32 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// One newline-terminated command per connection, the server closes after replying
+    Text,
+    /// Length-prefixed frames, many commands per connection
+    Framed(u8),
+}
+
+impl Protocol {
+    pub fn from_version(version: u8) -> Self {
+        match version {
+            0 => Protocol::Text,
+            version => Protocol::Framed(version),
+        }
+    }
+
+    pub fn has_typed_responses(&self) -> bool {
+        match self {
+            Protocol::Framed(version) => *version >= TYPED_RESPONSES_VERSION,
+            Protocol::Text => false,
+        }
+    }
+
+    pub fn encode_response(&self, response: &Response) -> String {
+        match self.has_typed_responses() {
+            true => response.encode(),
+            false => response.to_string(),
+        }
+    }
+}
+
+/// Handshake is a plain text line, so a legacy server rejects it as an unknown
+/// command and closes the connection, which lets the client fall back to text.
+pub fn handshake_line(version: u8) -> String {
+    format!("{} {}\n", HANDSHAKE_PREFIX, version)
+}
+
+/// Server handshake reply carrying a nonce the client has to sign
+pub fn challenge_line(version: u8, nonce: &str) -> String {
+    format!("{} {} {}\n", HANDSHAKE_PREFIX, version, nonce)
+}
+
+pub fn parse_handshake(line: &str) -> Option<u8> {
+    match line.split_whitespace().collect::<Vec<_>>()[..] {
+        [HANDSHAKE_PREFIX, version] | [HANDSHAKE_PREFIX, version, _] => version.parse().ok(),
+        _ => None,
+    }
+}
+
+pub fn parse_challenge(line: &str) -> Option<&str> {
+    match line.split_whitespace().collect::<Vec<_>>()[..] {
+        [HANDSHAKE_PREFIX, _, nonce] => Some(nonce),
+        _ => None,
+    }
+}
+
+/// Answer to a challenge: `auth <client id> <signature>`, or a bare `auth`
+/// for a client without credentials
+pub fn auth_line(credentials: Option<(&str, &str)>) -> String {
+    match credentials {
+        Some((client_id, signature)) => format!("{} {} {}\n", AUTH_PREFIX, client_id, signature),
+        None => format!("{}\n", AUTH_PREFIX),
+    }
+}
+
+/// `Some(None)` for an anonymous client, `None` for a malformed line
+pub fn parse_auth(line: &str) -> Option<Option<(&str, &str)>> {
+    match line.split_whitespace().collect::<Vec<_>>()[..] {
+        [AUTH_PREFIX] => Some(None),
+        [AUTH_PREFIX, client_id, signature] => Some(Some((client_id, signature))),
+        _ => None,
+    }
+}
+
+/// Frame layout: 4-byte big-endian payload length followed by the payload.
+pub async fn write_frame<W>(writer: &mut W, payload: &[u8]) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_SIZE)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Frame of {} bytes is too large", payload.len()),
+            )
+        })?;
+
+    // Single write, so Nagle's algorithm doesn't hold the payload back
+    let mut frame = Vec::with_capacity(payload.len() + 4);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+
+    writer.write_all(&frame).await?;
+    writer.flush().await
+}
+
+/// Returns `Ok(None)` when the peer closed the connection between frames.
+pub async fn read_frame<R>(reader: &mut R) -> io::Result<Option<Vec<u8>>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut len_buf = [0; 4];
+
+    match reader.read_exact(&mut len_buf).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_be_bytes(len_buf);
+
+    if len > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Frame of {} bytes is too large", len),
+        ));
+    }
+
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload).await?;
+
+    Ok(Some(payload))
+}
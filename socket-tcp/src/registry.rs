@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+use crate::event::Event;
+use crate::schedule::{Clock, Schedule, ScheduleAction, SystemClock, Trigger};
+use crate::socket::Socket;
+
+/// Named sockets hosted by a server. Clones share the same sockets, so the
+/// registry can be changed at runtime while the server is running.
+#[derive(Clone)]
+pub struct SocketRegistry {
+    sockets: Arc<Mutex<BTreeMap<String, Socket>>>,
+    clock: Arc<dyn Clock>,
+    next_schedule_id: Arc<AtomicU64>,
+    events: broadcast::Sender<Event>,
+}
+
+/// Events a slow subscriber may fall behind by before it misses some
+const EVENTS_CAPACITY: usize = 64;
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum RegistryError {
+    #[error("Socket with name {:?} already exists", .0)]
+    SocketAlreadyExists(String),
+    #[error("Cannot find socket with name {:?}", .0)]
+    SocketNotFound(String),
+    #[error("Cannot find schedule with id {}", .0)]
+    ScheduleNotFound(u64),
+    #[error("Cannot schedule {} that far ahead", .0)]
+    ScheduleOutOfRange(Trigger),
+}
+
+impl Default for SocketRegistry {
+    fn default() -> Self {
+        Self {
+            sockets: Arc::new(Mutex::new(BTreeMap::new())),
+            clock: Arc::new(SystemClock),
+            next_schedule_id: Arc::new(AtomicU64::new(1)),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+        }
+    }
+}
+
+impl SocketRegistry {
+    pub fn new(sockets: Vec<Socket>) -> Result<Self, RegistryError> {
+        let registry = Self::default();
+
+        sockets
+            .into_iter()
+            .try_for_each(|socket| registry.add(socket))?;
+
+        Ok(registry)
+    }
+
+    /// Replaces the clock schedules are planned and executed with
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn add(&self, socket: Socket) -> Result<(), RegistryError> {
+        let mut sockets = self.sockets.lock().unwrap();
+
+        if sockets.contains_key(&socket.name) {
+            Err(RegistryError::SocketAlreadyExists(socket.name))
+        } else {
+            sockets.insert(socket.name.to_string(), socket);
+
+            Ok(())
+        }
+    }
+
+    pub fn remove(&self, name: &str) -> Result<Socket, RegistryError> {
+        self.sockets
+            .lock()
+            .unwrap()
+            .remove(name)
+            .ok_or_else(|| RegistryError::SocketNotFound(name.to_string()))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.sockets.lock().unwrap().keys().cloned().collect()
+    }
+
+    pub fn with_socket<T, F>(&self, name: &str, f: F) -> Result<T, RegistryError>
+    where
+        F: FnOnce(&mut Socket) -> T,
+    {
+        let mut sockets = self.sockets.lock().unwrap();
+        let socket = sockets
+            .get_mut(name)
+            .ok_or_else(|| RegistryError::SocketNotFound(name.to_string()))?;
+
+        let before = WatchedState::of(socket);
+        let result = f(socket);
+        self.publish_changes(before, socket);
+
+        Ok(result)
+    }
+
+    /// Receives state changes of every socket from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    fn publish_changes(&self, before: WatchedState, socket: &Socket) {
+        for event in before.changes(socket) {
+            // Nobody listening is fine
+            let _r = self.events.send(event);
+        }
+    }
+
+    pub fn add_schedule(
+        &self,
+        name: &str,
+        action: ScheduleAction,
+        trigger: Trigger,
+    ) -> Result<Schedule, RegistryError> {
+        let next_run = trigger
+            .next_run(self.clock.now())
+            .ok_or(RegistryError::ScheduleOutOfRange(trigger))?;
+
+        self.with_socket(name, |socket| {
+            let schedule = Schedule {
+                id: self.next_schedule_id.fetch_add(1, Ordering::Relaxed),
+                action,
+                trigger,
+                next_run,
+            };
+
+            socket.add_schedule(schedule.clone());
+            schedule
+        })
+    }
+
+    pub fn schedules(&self, name: &str) -> Result<Vec<Schedule>, RegistryError> {
+        self.with_socket(name, |socket| socket.schedules().to_vec())
+    }
+
+    pub fn cancel_schedule(&self, name: &str, id: u64) -> Result<Schedule, RegistryError> {
+        self.with_socket(name, |socket| socket.cancel_schedule(id))?
+            .ok_or(RegistryError::ScheduleNotFound(id))
+    }
+
+    /// Samples a fresh load of every socket, accounts the energy drawn and
+    /// publishes power threshold crossings
+    pub fn sample_meters(&self) {
+        for socket in self.sockets.lock().unwrap().values_mut() {
+            let before = WatchedState::of(socket);
+            socket.sample_meter();
+            self.publish_changes(before, socket);
+        }
+    }
+
+    /// Executes schedules due by the registry clock, returns how many ran
+    pub fn run_due_schedules(&self) -> usize {
+        let now = self.clock.now();
+        let mut sockets = self.sockets.lock().unwrap();
+
+        sockets
+            .values_mut()
+            .map(|socket| {
+                let before = WatchedState::of(socket);
+                let executed = socket.run_due_schedules(now).len();
+                self.publish_changes(before, socket);
+
+                executed
+            })
+            .sum()
+    }
+}
+
+struct WatchedState {
+    status: bool,
+    above_threshold: Option<bool>,
+}
+
+impl WatchedState {
+    fn of(socket: &Socket) -> Self {
+        Self {
+            status: socket.status,
+            above_threshold: socket.is_above_threshold(),
+        }
+    }
+
+    fn changes(&self, socket: &Socket) -> Vec<Event> {
+        let mut events = Vec::new();
+        let name = socket.name.to_string();
+
+        match (self.status, socket.status) {
+            (false, true) => events.push(Event::TurnedOn(name.clone())),
+            (true, false) => events.push(Event::TurnedOff(name.clone())),
+            _ => {}
+        }
+
+        // A new threshold counts as crossed only if the power is above it
+        if let Some(above) = socket.is_above_threshold() {
+            if self.above_threshold.unwrap_or(false) != above {
+                events.push(Event::PowerThresholdCrossed {
+                    name,
+                    power: socket.last_power(),
+                    above,
+                });
+            }
+        }
+
+        events
+    }
+}
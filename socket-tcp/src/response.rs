@@ -0,0 +1,352 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::event::Event;
+use crate::registry::RegistryError;
+use crate::schedule::Schedule;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketStatus {
+    On,
+    Off,
+}
+
+impl From<bool> for SocketStatus {
+    fn from(status: bool) -> Self {
+        match status {
+            true => SocketStatus::On,
+            false => SocketStatus::Off,
+        }
+    }
+}
+
+impl fmt::Display for SocketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SocketStatus::On => write!(f, "on"),
+            SocketStatus::Off => write!(f, "off"),
+        }
+    }
+}
+
+impl FromStr for SocketStatus {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "on" => Ok(SocketStatus::On),
+            "off" => Ok(SocketStatus::Off),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUnit {
+    Watt,
+    Kilowatt,
+}
+
+impl fmt::Display for PowerUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PowerUnit::Watt => write!(f, "W"),
+            PowerUnit::Kilowatt => write!(f, "kW"),
+        }
+    }
+}
+
+impl FromStr for PowerUnit {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "W" => Ok(PowerUnit::Watt),
+            "kW" => Ok(PowerUnit::Kilowatt),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Power {
+    pub value: f32,
+    pub unit: PowerUnit,
+}
+
+impl Power {
+    pub fn watts(value: f32) -> Self {
+        Self {
+            value,
+            unit: PowerUnit::Watt,
+        }
+    }
+
+    pub fn to_watts(&self) -> f32 {
+        match self.unit {
+            PowerUnit::Watt => self.value,
+            PowerUnit::Kilowatt => self.value * 1000.0,
+        }
+    }
+}
+
+impl fmt::Display for Power {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.unit)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    SocketNotFound = 1,
+    SocketAlreadyExists = 2,
+    UnknownCommand = 3,
+    ScheduleNotFound = 4,
+    Unsupported = 5,
+    Unauthorized = 6,
+    ScheduleOutOfRange = 7,
+}
+
+impl ErrorCode {
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(ErrorCode::SocketNotFound),
+            2 => Some(ErrorCode::SocketAlreadyExists),
+            3 => Some(ErrorCode::UnknownCommand),
+            4 => Some(ErrorCode::ScheduleNotFound),
+            5 => Some(ErrorCode::Unsupported),
+            6 => Some(ErrorCode::Unauthorized),
+            7 => Some(ErrorCode::ScheduleOutOfRange),
+            _ => None,
+        }
+    }
+
+    /// Tells the code by the wording of the message, for replies of the
+    /// legacy text protocol that carry only the message
+    pub fn from_message(message: &str) -> Option<Self> {
+        let wording = [
+            (
+                "Cannot find socket with name ",
+                "",
+                ErrorCode::SocketNotFound,
+            ),
+            (
+                "Socket with name ",
+                " already exists",
+                ErrorCode::SocketAlreadyExists,
+            ),
+            ("Unknown command ", "", ErrorCode::UnknownCommand),
+            (
+                "Cannot find schedule with id ",
+                "",
+                ErrorCode::ScheduleNotFound,
+            ),
+            (
+                "Subscriptions need a framed connection",
+                "",
+                ErrorCode::Unsupported,
+            ),
+            ("Not allowed to run ", "", ErrorCode::Unauthorized),
+            (
+                "Cannot schedule ",
+                " that far ahead",
+                ErrorCode::ScheduleOutOfRange,
+            ),
+        ];
+
+        wording
+            .into_iter()
+            .find(|(prefix, suffix, _)| message.starts_with(prefix) && message.ends_with(suffix))
+            .map(|(_, _, code)| code)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Status { name: String, status: SocketStatus },
+    PowerConsumption { name: String, power: Power },
+    Energy { name: String, energy_kwh: f64 },
+    EnergyReset(String),
+    Sockets(Vec<String>),
+    Added(String),
+    Removed(String),
+    PowerThresholdSet { name: String, watts: Option<f32> },
+    Schedules(Vec<Schedule>),
+    ScheduleAdded(Schedule),
+    ScheduleCancelled(u64),
+    Subscribed,
+    Event(Event),
+    Error { code: ErrorCode, message: String },
+}
+
+impl From<RegistryError> for Response {
+    fn from(e: RegistryError) -> Self {
+        let code = match e {
+            RegistryError::SocketNotFound(_) => ErrorCode::SocketNotFound,
+            RegistryError::SocketAlreadyExists(_) => ErrorCode::SocketAlreadyExists,
+            RegistryError::ScheduleNotFound(_) => ErrorCode::ScheduleNotFound,
+            RegistryError::ScheduleOutOfRange(_) => ErrorCode::ScheduleOutOfRange,
+        };
+
+        Response::Error {
+            code,
+            message: e.to_string(),
+        }
+    }
+}
+
+const FIELD_SEPARATOR: char = '\t';
+
+impl Response {
+    /// Wire format: tab-separated fields, the first one names the response.
+    pub fn encode(&self) -> String {
+        let fields = match self {
+            Response::Status { name, status } => {
+                vec!["status".to_string(), name.to_string(), status.to_string()]
+            }
+            Response::PowerConsumption { name, power } => vec![
+                "power".to_string(),
+                name.to_string(),
+                power.value.to_string(),
+                power.unit.to_string(),
+            ],
+            Response::Energy { name, energy_kwh } => vec![
+                "energy".to_string(),
+                name.to_string(),
+                energy_kwh.to_string(),
+            ],
+            Response::EnergyReset(name) => vec!["energy_reset".to_string(), name.to_string()],
+            Response::Sockets(names) => {
+                let mut fields = vec!["sockets".to_string()];
+                fields.extend(names.iter().cloned());
+                fields
+            }
+            Response::Added(name) => vec!["added".to_string(), name.to_string()],
+            Response::Removed(name) => vec!["removed".to_string(), name.to_string()],
+            Response::PowerThresholdSet { name, watts } => vec![
+                "threshold_set".to_string(),
+                name.to_string(),
+                match watts {
+                    Some(watts) => watts.to_string(),
+                    None => "none".to_string(),
+                },
+            ],
+            Response::Schedules(schedules) => {
+                let mut fields = vec!["schedules".to_string()];
+                fields.extend(schedules.iter().map(|schedule| schedule.to_string()));
+                fields
+            }
+            Response::ScheduleAdded(schedule) => {
+                vec!["schedule_added".to_string(), schedule.to_string()]
+            }
+            Response::ScheduleCancelled(id) => {
+                vec!["schedule_cancelled".to_string(), id.to_string()]
+            }
+            Response::Subscribed => vec!["subscribed".to_string()],
+            Response::Event(event) => {
+                let mut fields = vec!["event".to_string()];
+                fields.extend(event.fields());
+                fields
+            }
+            Response::Error { code, message } => vec![
+                "error".to_string(),
+                (*code as u16).to_string(),
+                message.to_string(),
+            ],
+        };
+
+        fields.join(&FIELD_SEPARATOR.to_string())
+    }
+
+    pub fn decode(input: &str) -> Option<Self> {
+        let fields: Vec<&str> = input.split(FIELD_SEPARATOR).collect();
+
+        match fields[..] {
+            ["status", name, status] => Some(Response::Status {
+                name: name.to_string(),
+                status: status.parse().ok()?,
+            }),
+            ["power", name, value, unit] => Some(Response::PowerConsumption {
+                name: name.to_string(),
+                power: Power {
+                    value: value.parse().ok()?,
+                    unit: unit.parse().ok()?,
+                },
+            }),
+            ["energy", name, energy_kwh] => Some(Response::Energy {
+                name: name.to_string(),
+                energy_kwh: energy_kwh.parse().ok()?,
+            }),
+            ["energy_reset", name] => Some(Response::EnergyReset(name.to_string())),
+            ["sockets", ..] => Some(Response::Sockets(
+                fields[1..].iter().map(|name| name.to_string()).collect(),
+            )),
+            ["added", name] => Some(Response::Added(name.to_string())),
+            ["removed", name] => Some(Response::Removed(name.to_string())),
+            ["threshold_set", name, watts] => Some(Response::PowerThresholdSet {
+                name: name.to_string(),
+                watts: match watts {
+                    "none" => None,
+                    watts => Some(watts.parse().ok()?),
+                },
+            }),
+            ["schedules", ..] => Some(Response::Schedules(
+                fields[1..]
+                    .iter()
+                    .map(|schedule| schedule.parse())
+                    .collect::<Result<_, _>>()
+                    .ok()?,
+            )),
+            ["schedule_added", schedule] => Some(Response::ScheduleAdded(schedule.parse().ok()?)),
+            ["schedule_cancelled", id] => Some(Response::ScheduleCancelled(id.parse().ok()?)),
+            ["subscribed"] => Some(Response::Subscribed),
+            ["event", ..] => Some(Response::Event(Event::from_fields(&fields[1..])?)),
+            ["error", code, message] => Some(Response::Error {
+                code: ErrorCode::from_code(code.parse().ok()?)?,
+                message: message.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Human readable form, also the reply format of the legacy text protocol
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Response::Status { name, status } => write!(f, "{} is {}", name, status),
+            Response::PowerConsumption { power, .. } => {
+                write!(f, "Power consumption is {}", power.to_watts())
+            }
+            Response::Energy { energy_kwh, .. } => {
+                write!(f, "Energy consumed is {} kWh", energy_kwh)
+            }
+            Response::EnergyReset(name) => write!(f, "{} energy meter reset", name),
+            Response::Sockets(names) => write!(f, "{}", names.join("\n")),
+            Response::Added(name) => write!(f, "{} added", name),
+            Response::Removed(name) => write!(f, "{} removed", name),
+            Response::PowerThresholdSet { name, watts } => match watts {
+                Some(watts) => write!(f, "{} power threshold set to {} W", name, watts),
+                None => write!(f, "{} power threshold removed", name),
+            },
+            Response::Schedules(schedules) => {
+                let lines: Vec<String> = schedules
+                    .iter()
+                    .map(|schedule| {
+                        format!(
+                            "#{} turn {} {}",
+                            schedule.id, schedule.action, schedule.trigger
+                        )
+                    })
+                    .collect();
+
+                write!(f, "{}", lines.join("\n"))
+            }
+            Response::ScheduleAdded(schedule) => write!(f, "Schedule #{} added", schedule.id),
+            Response::ScheduleCancelled(id) => write!(f, "Schedule #{} cancelled", id),
+            Response::Subscribed => write!(f, "Subscribed to events"),
+            Response::Event(event) => write!(f, "{}", event),
+            Response::Error { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::registry::SocketRegistry;
+
+const SECONDS_IN_DAY: u64 = 24 * 60 * 60;
+
+/// Longest delay an `in:` trigger accepts, about a hundred years
+pub const MAX_DELAY: Duration = Duration::from_secs(36_500 * SECONDS_IN_DAY);
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock that only moves when told to, for driving schedules in tests
+pub struct MockClock(Mutex<SystemTime>);
+
+impl MockClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self(Mutex::new(now))
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap() += duration;
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.0.lock().unwrap() = now;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.0.lock().unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleAction {
+    TurnOn,
+    TurnOff,
+}
+
+impl fmt::Display for ScheduleAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScheduleAction::TurnOn => write!(f, "on"),
+            ScheduleAction::TurnOff => write!(f, "off"),
+        }
+    }
+}
+
+impl FromStr for ScheduleAction {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "on" => Ok(ScheduleAction::TurnOn),
+            "off" => Ok(ScheduleAction::TurnOff),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Daily times are UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    After(Duration),
+    DailyAt { hour: u8, minute: u8 },
+}
+
+impl Trigger {
+    pub fn daily_at(hour: u8, minute: u8) -> Option<Self> {
+        match hour < 24 && minute < 60 {
+            true => Some(Trigger::DailyAt { hour, minute }),
+            false => None,
+        }
+    }
+
+    /// First moment strictly after `now` the trigger fires at, `None` if the
+    /// moment can't be represented
+    pub fn next_run(&self, now: SystemTime) -> Option<SystemTime> {
+        match self {
+            Trigger::After(delay) => now.checked_add(*delay),
+            Trigger::DailyAt { hour, minute } => {
+                let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                let day_start = since_epoch - since_epoch % SECONDS_IN_DAY;
+                let mut run_at = day_start + *hour as u64 * 3600 + *minute as u64 * 60;
+
+                if run_at <= since_epoch {
+                    run_at += SECONDS_IN_DAY;
+                }
+
+                UNIX_EPOCH.checked_add(Duration::from_secs(run_at))
+            }
+        }
+    }
+
+    pub fn is_repeating(&self) -> bool {
+        matches!(self, Trigger::DailyAt { .. })
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trigger::After(delay) => write!(f, "in:{}", delay.as_secs()),
+            Trigger::DailyAt { hour, minute } => write!(f, "daily:{:02}:{:02}", hour, minute),
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once(':').ok_or(())? {
+            ("in", secs) => {
+                let delay = Duration::from_secs(secs.parse().map_err(|_| ())?);
+
+                match delay <= MAX_DELAY {
+                    true => Ok(Trigger::After(delay)),
+                    false => Err(()),
+                }
+            }
+            ("daily", time) => {
+                let (hour, minute) = time.split_once(':').ok_or(())?;
+
+                Trigger::daily_at(
+                    hour.parse().map_err(|_| ())?,
+                    minute.parse().map_err(|_| ())?,
+                )
+                .ok_or(())
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub id: u64,
+    pub action: ScheduleAction,
+    pub trigger: Trigger,
+    pub next_run: SystemTime,
+}
+
+/// Wire form: `<id> <action> <trigger> <next run, unix seconds>`
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let next_run = self
+            .next_run
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        write!(
+            f,
+            "{} {} {} {}",
+            self.id, self.action, self.trigger, next_run
+        )
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = input.split(' ').collect();
+
+        match fields[..] {
+            [id, action, trigger, next_run] => Ok(Schedule {
+                id: id.parse().map_err(|_| ())?,
+                action: action.parse()?,
+                trigger: trigger.parse()?,
+                next_run: UNIX_EPOCH
+                    .checked_add(Duration::from_secs(next_run.parse().map_err(|_| ())?))
+                    .ok_or(())?,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Executes due schedules and samples the meters of every socket in the
+/// registry each `period`
+pub async fn run_scheduler(registry: SocketRegistry, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+        registry.run_due_schedules();
+        registry.sample_meters();
+    }
+}
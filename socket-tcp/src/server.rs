@@ -1,73 +1,441 @@
+use std::future::Future;
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, watch, Semaphore};
+use tokio::time::timeout;
 
-use crate::{command::Command, socket::Socket};
+use crate::auth::{self, AuthConfig, Role};
+use crate::protocol::{self, Protocol, AUTH_ACCEPTED, AUTH_VERSION, PROTOCOL_VERSION};
+use crate::response::{ErrorCode, Response};
+use crate::schedule;
+use crate::{command::Command, registry::SocketRegistry, socket::Socket};
 
-pub async fn run_server<Cb>(address: &str, cb_after_start: Cb) -> std::io::Result<()>
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Connections above the limit wait in the listen backlog
+    pub max_connections: usize,
+    /// Time allowed to receive a whole request once it started arriving
+    pub read_timeout: Duration,
+    /// Time a persistent connection may stay silent between requests
+    pub idle_timeout: Duration,
+    /// How often due schedules are looked for and meters sampled
+    pub schedule_interval: Duration,
+    /// Clients have to authenticate when set, otherwise everyone has control
+    pub auth: Option<Arc<AuthConfig>>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 64,
+            read_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(60),
+            schedule_interval: Duration::from_secs(1),
+            auth: None,
+        }
+    }
+}
+
+struct ConnectionContext {
+    registry: SocketRegistry,
+    config: ServerConfig,
+    role: Option<Role>,
+    shutdown: watch::Receiver<bool>,
+    // Dropped with the connection, lets the server wait for in-flight connections
+    _done: mpsc::Sender<()>,
+}
+
+pub async fn run_server<Cb>(
+    address: &str,
+    registry: SocketRegistry,
+    cb_after_start: Cb,
+) -> std::io::Result<()>
 where
     Cb: Fn(),
 {
-    let listener = TcpListener::bind(address).await?;
+    run_server_with_shutdown(
+        address,
+        registry,
+        ServerConfig::default(),
+        std::future::pending(),
+        cb_after_start,
+    )
+    .await
+}
 
-    let socket_default = Socket {
-        name: "my socket".to_string(),
-        power_consumption: 20.0,
-        status: false,
-    };
-    let smart_socket = Arc::new(Mutex::new(socket_default));
+/// Serves until `shutdown` completes, then stops accepting connections and
+/// returns once every open connection has finished its current request.
+pub async fn run_server_with_shutdown<Cb, S>(
+    address: &str,
+    registry: SocketRegistry,
+    config: ServerConfig,
+    shutdown: S,
+    cb_after_start: Cb,
+) -> std::io::Result<()>
+where
+    Cb: Fn(),
+    S: Future<Output = ()>,
+{
+    let listener = TcpListener::bind(address).await?;
 
     cb_after_start();
 
-    loop {
-        let (stream, _) = listener.accept().await?;
+    serve(listener, registry, config, shutdown).await
+}
+
+/// Same as `run_server_with_shutdown` on a listener bound by the caller, like
+/// one on port 0 whose address is only known once bound
+pub async fn serve<S>(
+    listener: TcpListener,
+    registry: SocketRegistry,
+    config: ServerConfig,
+    shutdown: S,
+) -> std::io::Result<()>
+where
+    S: Future<Output = ()>,
+{
+    let connection_limit = Arc::new(Semaphore::new(config.max_connections));
+    let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+    let (done_sender, mut done_receiver) = mpsc::channel::<()>(1);
+
+    let scheduler = tokio::spawn(schedule::run_scheduler(
+        registry.clone(),
+        config.schedule_interval,
+    ));
+
+    tokio::pin!(shutdown);
+
+    let result = loop {
+        let permit = tokio::select! {
+            permit = connection_limit.clone().acquire_owned() => {
+                permit.expect("Connection limit semaphore is never closed")
+            }
+            _ = &mut shutdown => break Ok(()),
+        };
+
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => break Err(e),
+            },
+            _ = &mut shutdown => break Ok(()),
+        };
+
+        let context = ConnectionContext {
+            registry: registry.clone(),
+            config: config.clone(),
+            role: None,
+            shutdown: shutdown_receiver.clone(),
+            _done: done_sender.clone(),
+        };
 
-        let smart_socket = smart_socket.clone();
         tokio::spawn(async move {
-            handle_connection(stream, smart_socket).await;
+            handle_connection(stream, context).await;
+            drop(permit);
         });
+    };
+
+    drop(listener);
+    scheduler.abort();
+    let _r = shutdown_sender.send(true);
+
+    drop(done_sender);
+    done_receiver.recv().await;
+
+    result
+}
+
+async fn handle_connection(stream: TcpStream, mut context: ConnectionContext) {
+    let mut stream = BufReader::new(stream);
+    let mut first_line = String::new();
+
+    match timeout(
+        context.config.read_timeout,
+        stream.read_line(&mut first_line),
+    )
+    .await
+    {
+        Ok(Ok(size)) if size > 0 => {}
+        _ => return,
+    }
+
+    let client_version = match protocol::parse_handshake(&first_line) {
+        Some(client_version) => client_version,
+        None => {
+            // Legacy client: the first line is already the command
+            context.role = unauthenticated_role(&context.config);
+            let response = respond(first_line.trim(), &context);
+
+            let _r = stream.write_all(response.to_string().as_bytes()).await;
+            return;
+        }
+    };
+
+    let version = client_version.min(PROTOCOL_VERSION);
+
+    context.role = match handshake(&mut stream, &context.config, version).await {
+        Ok(role) => role,
+        Err(_) => return,
+    };
+
+    match Protocol::from_version(version) {
+        Protocol::Framed(version) => serve_framed(stream, &mut context, version).await,
+        Protocol::Text => serve_text(stream, &context).await,
+    }
+}
+
+/// Replies to the client handshake and, when the server requires it and the
+/// client can answer, checks the client signature of a fresh nonce.
+async fn handshake(
+    stream: &mut BufReader<TcpStream>,
+    config: &ServerConfig,
+    version: u8,
+) -> Result<Option<Role>, ()> {
+    let auth = match &config.auth {
+        Some(auth) if version >= AUTH_VERSION => auth,
+        _ => {
+            stream
+                .write_all(protocol::handshake_line(version).as_bytes())
+                .await
+                .map_err(|_| ())?;
+
+            return Ok(unauthenticated_role(config));
+        }
+    };
+
+    let nonce = auth::new_nonce();
+
+    stream
+        .write_all(protocol::challenge_line(version, &nonce).as_bytes())
+        .await
+        .map_err(|_| ())?;
+
+    let mut line = String::new();
+    match timeout(config.read_timeout, stream.read_line(&mut line)).await {
+        Ok(Ok(size)) if size > 0 => {}
+        _ => return Err(()),
+    }
+
+    let role = match protocol::parse_auth(&line) {
+        Some(Some((client_id, signature))) => auth.verify(client_id, &nonce, signature),
+        Some(None) => auth.anonymous_role,
+        None => None,
+    };
+
+    let reply = match role {
+        Some(role) => format!("{} {}\n", AUTH_ACCEPTED, role),
+        None => "denied\n".to_string(),
+    };
+
+    stream.write_all(reply.as_bytes()).await.map_err(|_| ())?;
+
+    match role {
+        Some(role) => Ok(Some(role)),
+        None => Err(()),
     }
 }
 
-async fn handle_connection(mut stream: TcpStream, socket: Arc<Mutex<Socket>>) {
-    let mut response = String::new();
-    let mut buf_reader = BufReader::new(&mut stream);
+fn unauthenticated_role(config: &ServerConfig) -> Option<Role> {
+    match &config.auth {
+        Some(auth) => auth.anonymous_role,
+        None => Some(Role::Control),
+    }
+}
 
-    println!("from handle conn");
+async fn serve_framed(
+    mut stream: BufReader<TcpStream>,
+    context: &mut ConnectionContext,
+    version: u8,
+) {
+    let protocol = Protocol::Framed(version);
 
-    buf_reader
-        .read_line(&mut response)
+    loop {
+        // Idle connections are closed on shutdown, started requests are served
+        let has_request = tokio::select! {
+            ready = timeout(context.config.idle_timeout, stream.fill_buf()) => {
+                matches!(ready, Ok(Ok(buf)) if !buf.is_empty())
+            }
+            _ = context.shutdown.changed() => false,
+        };
+
+        if !has_request {
+            break;
+        }
+
+        let payload = match timeout(
+            context.config.read_timeout,
+            protocol::read_frame(&mut stream),
+        )
         .await
-        .expect("Unable to read");
+        {
+            Ok(Ok(Some(payload))) => payload,
+            _ => break,
+        };
 
-    let command = Command::from_str(response.trim()).unwrap();
+        let request = String::from_utf8_lossy(&payload);
 
-    let message_for_client = match command {
-        Command::GetStatus => {
-            let socket = socket.lock().unwrap();
-            socket.get_status()
+        let response = match authorize(request.trim(), context.role) {
+            Ok(Command::Subscribe(name)) => {
+                serve_subscription(&mut stream, context, protocol, name).await;
+                break;
+            }
+            Ok(command) => execute_command(command, &context.registry),
+            Err(response) => response,
+        };
+        let message_for_client = protocol.encode_response(&response);
+
+        if protocol::write_frame(&mut stream, message_for_client.as_bytes())
+            .await
+            .is_err()
+        {
+            break;
         }
-        Command::TurnOn => {
-            let mut socket = socket.lock().unwrap();
+    }
+}
 
-            socket.turn_on();
-            socket.get_status()
+/// Pushes events until the client disconnects or the server shuts down
+async fn serve_subscription(
+    stream: &mut BufReader<TcpStream>,
+    context: &mut ConnectionContext,
+    protocol: Protocol,
+    name: Option<String>,
+) {
+    let reply = match &name {
+        Some(name) => context
+            .registry
+            .with_socket(name, |_| Response::Subscribed)
+            .unwrap_or_else(Response::from),
+        None => Response::Subscribed,
+    };
+
+    // Subscribe before replying, so no event after the reply is missed
+    let mut events = context.registry.subscribe();
+
+    if protocol::write_frame(stream, protocol.encode_response(&reply).as_bytes())
+        .await
+        .is_err()
+        || reply != Response::Subscribed
+    {
+        return;
+    }
+
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => event,
+                // A slow subscriber skips what it missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            // Subscribers don't send anything, so readable means closed
+            _ = stream.fill_buf() => break,
+            _ = context.shutdown.changed() => break,
+        };
+
+        if matches!(&name, Some(name) if name != event.socket_name()) {
+            continue;
         }
-        Command::TurnOff => {
-            let mut socket = socket.lock().unwrap();
-            socket.turn_off();
-            socket.get_status()
+
+        let message_for_client = protocol.encode_response(&Response::Event(event));
+
+        if protocol::write_frame(stream, message_for_client.as_bytes())
+            .await
+            .is_err()
+        {
+            break;
         }
-        Command::GetPowerConsumption => {
-            let socket = socket.lock().unwrap();
-            socket.get_power_consumption()
+    }
+}
+
+async fn serve_text(mut stream: BufReader<TcpStream>, context: &ConnectionContext) {
+    let mut request = String::new();
+
+    if let Ok(Ok(_)) = timeout(context.config.read_timeout, stream.read_line(&mut request)).await {
+        let response = respond(request.trim(), context);
+
+        let _r = stream.write_all(response.to_string().as_bytes()).await;
+    }
+}
+
+fn respond(request: &str, context: &ConnectionContext) -> Response {
+    authorize(request, context.role)
+        .map(|command| execute_command(command, &context.registry))
+        .unwrap_or_else(|response| response)
+}
+
+/// Parses the request and checks the connection role allows running it
+fn authorize(request: &str, role: Option<Role>) -> Result<Command, Response> {
+    let command = Command::from_str(request).map_err(|_| Response::Error {
+        code: ErrorCode::UnknownCommand,
+        message: format!("Unknown command {:?}", request),
+    })?;
+
+    match role {
+        Some(role) if role >= command.required_role() => Ok(command),
+        _ => Err(Response::Error {
+            code: ErrorCode::Unauthorized,
+            message: format!("Not allowed to run {:?}", request),
+        }),
+    }
+}
+
+fn execute_command(command: Command, registry: &SocketRegistry) -> Response {
+    let result = match command {
+        Command::GetStatus(name) => registry.with_socket(&name, |socket| socket.status_response()),
+        Command::TurnOn(name) => registry.with_socket(&name, |socket| {
+            socket.turn_on();
+            socket.status_response()
+        }),
+        Command::TurnOff(name) => registry.with_socket(&name, |socket| {
+            socket.turn_off();
+            socket.status_response()
+        }),
+        Command::GetPowerConsumption(name) => {
+            registry.with_socket(&name, |socket| socket.power_response())
         }
+        Command::GetEnergy(name) => registry.with_socket(&name, |socket| socket.energy_response()),
+        Command::ResetEnergy(name) => registry.with_socket(&name, |socket| {
+            socket.reset_energy();
+            Response::EnergyReset(name.to_string())
+        }),
+        Command::ListSockets => Ok(Response::Sockets(registry.names())),
+        Command::AddSocket {
+            name,
+            power_consumption,
+        } => registry
+            .add(Socket::new(&name, power_consumption))
+            .map(|_| Response::Added(name)),
+        Command::RemoveSocket(name) => registry
+            .remove(&name)
+            .map(|socket| Response::Removed(socket.name)),
+        Command::SetPowerThreshold { name, watts } => registry.with_socket(&name, |socket| {
+            socket.power_threshold = watts;
+            Response::PowerThresholdSet {
+                name: name.to_string(),
+                watts,
+            }
+        }),
+        Command::AddSchedule {
+            name,
+            action,
+            trigger,
+        } => registry
+            .add_schedule(&name, action, trigger)
+            .map(Response::ScheduleAdded),
+        Command::ListSchedules(name) => registry.schedules(&name).map(Response::Schedules),
+        Command::CancelSchedule { name, id } => registry
+            .cancel_schedule(&name, id)
+            .map(|schedule| Response::ScheduleCancelled(schedule.id)),
+        Command::Subscribe(_) => Ok(Response::Error {
+            code: ErrorCode::Unsupported,
+            message: "Subscriptions need a framed connection".to_string(),
+        }),
     };
 
-    stream
-        .write_all(message_for_client.as_bytes())
-        .await
-        .unwrap();
+    result.unwrap_or_else(Response::from)
 }
@@ -1,27 +1,160 @@
+use std::time::{Instant, SystemTime};
+
+use crate::meter::{EnergyMeter, LoadModel};
+use crate::response::{Power, Response, SocketStatus};
+use crate::schedule::{Schedule, ScheduleAction};
+
+pub const DEFAULT_LOAD_VARIATION: f32 = 0.05;
+
 #[derive(Clone)]
 pub struct Socket {
     pub name: String,
     pub status: bool,
-    pub power_consumption: f32,
+    pub load: LoadModel,
+    /// Subscribers are notified when the power crosses it
+    pub power_threshold: Option<f32>,
+
+    meter: EnergyMeter,
+    schedules: Vec<Schedule>,
 }
 
 impl Socket {
-    pub fn get_status(&self) -> String {
-        match &self.status {
-            true => format!("{} is on", self.name),
-            false => format!("{} is off", self.name),
+    pub fn new(name: &str, power_consumption: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            status: false,
+            load: LoadModel {
+                nominal: power_consumption,
+                variation: DEFAULT_LOAD_VARIATION,
+            },
+            power_threshold: None,
+            meter: EnergyMeter::new(Instant::now()),
+            schedules: Vec::new(),
         }
     }
 
+    pub fn with_power_threshold(mut self, watts: f32) -> Self {
+        self.power_threshold = Some(watts);
+        self
+    }
+
+    /// Compares the last sampled power with the threshold, if one is set
+    pub fn is_above_threshold(&self) -> Option<bool> {
+        self.power_threshold
+            .map(|threshold| self.meter.load() > threshold)
+    }
+
+    /// Power sampled on the last meter update
+    pub fn last_power(&self) -> Power {
+        Power::watts(self.meter.load())
+    }
+
+    /// Accounts the energy drawn so far and samples a fresh load. The server
+    /// calls it on every tick, queries only read the meter.
+    pub fn sample_meter(&mut self) {
+        let load = self.load.sample(self.status, &mut rand::thread_rng());
+
+        self.meter.update(Instant::now(), load);
+    }
+
+    pub fn current_power(&self) -> Power {
+        self.last_power()
+    }
+
+    pub fn energy_kwh(&self) -> f64 {
+        self.meter.energy_kwh_at(Instant::now())
+    }
+
+    pub fn reset_energy(&mut self) {
+        self.meter.reset(Instant::now());
+    }
+
+    pub fn status_response(&self) -> Response {
+        Response::Status {
+            name: self.name.to_string(),
+            status: SocketStatus::from(self.status),
+        }
+    }
+
+    pub fn power_response(&self) -> Response {
+        Response::PowerConsumption {
+            name: self.name.to_string(),
+            power: self.current_power(),
+        }
+    }
+
+    pub fn energy_response(&self) -> Response {
+        Response::Energy {
+            name: self.name.to_string(),
+            energy_kwh: self.energy_kwh(),
+        }
+    }
+
+    pub fn get_status(&self) -> String {
+        self.status_response().to_string()
+    }
+
     pub fn get_power_consumption(&self) -> String {
-        format!("Power consumption is {}", self.power_consumption)
+        self.power_response().to_string()
     }
 
     pub fn turn_off(&mut self) {
         self.status = false;
+        self.sample_meter();
     }
 
     pub fn turn_on(&mut self) {
         self.status = true;
+        self.sample_meter();
+    }
+
+    pub fn schedules(&self) -> &[Schedule] {
+        &self.schedules
+    }
+
+    pub fn add_schedule(&mut self, schedule: Schedule) {
+        self.schedules.push(schedule);
+    }
+
+    pub fn cancel_schedule(&mut self, id: u64) -> Option<Schedule> {
+        let idx = self.schedules.iter().position(|s| s.id == id)?;
+
+        Some(self.schedules.remove(idx))
+    }
+
+    /// Applies every schedule due at `now`. One-shot schedules are dropped
+    /// afterwards, repeating ones move to their next run if it can be
+    /// represented.
+    pub fn run_due_schedules(&mut self, now: SystemTime) -> Vec<ScheduleAction> {
+        let (mut due, pending): (Vec<Schedule>, Vec<Schedule>) = self
+            .schedules
+            .drain(..)
+            .partition(|schedule| schedule.next_run <= now);
+
+        self.schedules = pending;
+        due.sort_by_key(|schedule| schedule.next_run);
+
+        let mut executed = Vec::new();
+
+        for mut schedule in due {
+            match schedule.action {
+                ScheduleAction::TurnOn => self.turn_on(),
+                ScheduleAction::TurnOff => self.turn_off(),
+            }
+            executed.push(schedule.action);
+
+            if !schedule.trigger.is_repeating() {
+                continue;
+            }
+
+            if let Some(next_run) = schedule.trigger.next_run(now) {
+                schedule.next_run = next_run;
+                self.schedules.push(schedule);
+            }
+        }
+
+        self.schedules.sort_by_key(|schedule| schedule.id);
+
+        executed
     }
 }
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use socket_tcp::{
+    auth::{self, AuthConfig, Credentials, Role},
+    client::{Client, ConnectionError},
+    registry::SocketRegistry,
+    response::SocketStatus,
+    server::{self, ServerConfig},
+    socket::Socket,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+async fn start_server(auth: AuthConfig) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let registry = SocketRegistry::new(vec![Socket::new("my socket", 20.0)]).unwrap();
+    let config = ServerConfig {
+        auth: Some(Arc::new(auth)),
+        ..ServerConfig::default()
+    };
+
+    tokio::spawn(server::serve(
+        listener,
+        registry,
+        config,
+        std::future::pending(),
+    ));
+
+    address
+}
+
+fn auth_config() -> AuthConfig {
+    AuthConfig::new()
+        .with_client("admin", b"admin secret", Role::Control)
+        .with_client("dashboard", b"dashboard secret", Role::ReadOnly)
+}
+
+#[test]
+fn test_verify_signature() {
+    let config = auth_config();
+    let nonce = auth::new_nonce();
+    let signature = auth::sign(b"admin secret", &nonce);
+
+    assert_eq!(
+        config.verify("admin", &nonce, &signature),
+        Some(Role::Control)
+    );
+    assert_eq!(config.verify("dashboard", &nonce, &signature), None);
+    assert_eq!(config.verify("stranger", &nonce, &signature), None);
+    assert_eq!(config.verify("admin", &auth::new_nonce(), &signature), None);
+}
+
+#[tokio::test]
+async fn test_roles_are_enforced() -> Result<(), ConnectionError> {
+    let address = start_server(auth_config()).await;
+
+    let admin = Client::new(&address).with_credentials(Credentials::new("admin", b"admin secret"));
+    let dashboard =
+        Client::new(&address).with_credentials(Credentials::new("dashboard", b"dashboard secret"));
+
+    assert_eq!(admin.turn_on("my socket").await?, SocketStatus::On);
+    assert_eq!(dashboard.get_status("my socket").await?, SocketStatus::On);
+    assert!(matches!(
+        dashboard.turn_off("my socket").await,
+        Err(ConnectionError::Unauthorized)
+    ));
+    assert_eq!(dashboard.get_status("my socket").await?, SocketStatus::On);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_clients_are_rejected() {
+    let address = start_server(auth_config()).await;
+
+    let impostor =
+        Client::new(&address).with_credentials(Credentials::new("admin", b"guessed secret"));
+    let anonymous = Client::new(&address);
+
+    assert!(matches!(
+        impostor.get_status("my socket").await,
+        Err(ConnectionError::Unauthorized)
+    ));
+    assert!(matches!(
+        anonymous.get_status("my socket").await,
+        Err(ConnectionError::Unauthorized)
+    ));
+
+    // Legacy clients can't authenticate at all
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    stream.write_all(b"turn_on\n").await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert_eq!(response, "Not allowed to run \"turn_on\"");
+}
+
+#[tokio::test]
+async fn test_anonymous_role() -> Result<(), ConnectionError> {
+    let address = start_server(auth_config().with_anonymous_role(Role::ReadOnly)).await;
+
+    let anonymous = Client::new(&address);
+
+    assert_eq!(anonymous.get_status("my socket").await?, SocketStatus::Off);
+    assert!(matches!(
+        anonymous.turn_on("my socket").await,
+        Err(ConnectionError::Unauthorized)
+    ));
+
+    Ok(())
+}
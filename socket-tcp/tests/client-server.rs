@@ -1,30 +1,48 @@
+use std::time::Duration;
+
 use socket_tcp::{
     client::{Client, ConnectionError},
-    server,
+    command::Command,
+    protocol::{self, PROTOCOL_VERSION},
+    registry::SocketRegistry,
+    response::{Power, Response, SocketStatus},
+    server::{self, ServerConfig},
+    socket::Socket,
 };
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
 
 #[tokio::test]
 async fn test_client_server() -> Result<(), ConnectionError> {
-    let address = "127.0.0.1:3333".to_string();
-
-    let socket_client = Client {
-        address: "127.0.0.1:3333".to_string(),
-    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let socket_client = Client::new(&listener.local_addr().unwrap().to_string());
 
     tokio::spawn(async move {
-        server::run_server(&address, || {
-            println!("Server started on {}", &address);
-        })
+        let registry = SocketRegistry::new(vec![Socket::new("my socket", 20.0)]).unwrap();
+
+        server::serve(
+            listener,
+            registry,
+            ServerConfig::default(),
+            std::future::pending(),
+        )
         .await
         .unwrap();
     });
 
     async fn run_tests(socket_client: Client) -> Result<(), ConnectionError> {
-        assert_eq!(socket_client.turn_on().await?, "my socket is on");
-        assert_eq!(socket_client.turn_off().await?, "my socket is off");
+        assert_eq!(socket_client.turn_on("my socket").await?, SocketStatus::On);
+
+        let power = socket_client.get_power_consumption("my socket").await?;
+        assert!((19.0..=21.0).contains(&power.to_watts()));
+
+        assert_eq!(
+            socket_client.turn_off("my socket").await?,
+            SocketStatus::Off
+        );
         assert_eq!(
-            socket_client.get_power_consumption().await?,
-            "Power consumption is 20"
+            socket_client.get_power_consumption("my socket").await?,
+            Power::watts(0.0)
         );
 
         Ok(())
@@ -38,11 +56,18 @@ async fn test_client_server() -> Result<(), ConnectionError> {
 
 #[tokio::test]
 async fn test_cant_connect_error() -> Result<(), ConnectionError> {
-    let socket_client = Client {
-        address: "127.0.0.1:3333".to_string(),
-    };
+    // Free port that nothing listens on anymore
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    drop(listener);
 
-    let result = socket_client.turn_on().await.unwrap_err().to_string();
+    let socket_client = Client::new(&address);
+
+    let result = socket_client
+        .turn_on("my socket")
+        .await
+        .unwrap_err()
+        .to_string();
 
     assert_eq!(
         result,
@@ -51,3 +76,69 @@ async fn test_cant_connect_error() -> Result<(), ConnectionError> {
 
     Ok(())
 }
+
+// Framed server that answers the first command it gets only after `delay`
+async fn run_slow_server(listener: TcpListener, delay: Duration) {
+    let mut status = SocketStatus::On;
+    let mut first = true;
+
+    loop {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+
+        let mut handshake = String::new();
+        stream.read_line(&mut handshake).await.unwrap();
+        stream
+            .write_all(protocol::handshake_line(PROTOCOL_VERSION).as_bytes())
+            .await
+            .unwrap();
+
+        while let Ok(Some(payload)) = protocol::read_frame(&mut stream).await {
+            match String::from_utf8(payload).unwrap().parse() {
+                Ok(Command::TurnOn(_)) => status = SocketStatus::On,
+                Ok(Command::TurnOff(_)) => status = SocketStatus::Off,
+                _ => (),
+            }
+
+            if first {
+                first = false;
+                tokio::time::sleep(delay).await;
+            }
+
+            let response = Response::Status {
+                name: "my socket".to_string(),
+                status,
+            };
+            if protocol::write_frame(&mut stream, response.encode().as_bytes())
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cancelled_command_does_not_leak_reply() -> Result<(), ConnectionError> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let socket_client = Client::new(&listener.local_addr().unwrap().to_string());
+
+    tokio::spawn(run_slow_server(listener, Duration::from_millis(300)));
+
+    let cancelled = tokio::time::timeout(
+        Duration::from_millis(100),
+        socket_client.turn_off("my socket"),
+    )
+    .await;
+    assert!(cancelled.is_err());
+
+    // The late "Off" of the cancelled command must not answer this one
+    assert_eq!(socket_client.turn_on("my socket").await?, SocketStatus::On);
+    assert_eq!(
+        socket_client.get_status("my socket").await?,
+        SocketStatus::On
+    );
+
+    Ok(())
+}
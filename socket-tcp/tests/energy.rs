@@ -0,0 +1,134 @@
+use std::time::{Duration, Instant};
+
+use socket_tcp::{
+    client::{Client, ConnectionError},
+    meter::{EnergyMeter, LoadModel},
+    registry::SocketRegistry,
+    server::{self, ServerConfig},
+    socket::Socket,
+};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_meter_integrates_load() {
+    let start = Instant::now();
+    let mut meter = EnergyMeter::new(start);
+
+    meter.update(start, 1000.0);
+    meter.update(start + Duration::from_secs(1800), 2000.0);
+    meter.update(start + Duration::from_secs(3600), 0.0);
+
+    assert!((meter.energy_kwh() - 1.5).abs() < 1e-9);
+
+    meter.update(start + Duration::from_secs(7200), 0.0);
+    assert!((meter.energy_kwh() - 1.5).abs() < 1e-9);
+
+    meter.reset(start + Duration::from_secs(7200));
+    assert_eq!(meter.energy_kwh(), 0.0);
+}
+
+#[test]
+fn test_load_model() {
+    let mut rng = rand::thread_rng();
+    let load = LoadModel {
+        nominal: 100.0,
+        variation: 0.1,
+    };
+
+    assert_eq!(load.sample(false, &mut rng), 0.0);
+
+    for _ in 0..100 {
+        let sample = load.sample(true, &mut rng);
+        assert!((90.0..=110.0).contains(&sample));
+    }
+
+    let steady = LoadModel {
+        nominal: 100.0,
+        variation: 0.0,
+    };
+    assert_eq!(steady.sample(true, &mut rng), 100.0);
+
+    for variation in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        let broken = LoadModel {
+            nominal: 100.0,
+            variation,
+        };
+        assert_eq!(broken.sample(true, &mut rng), 100.0);
+    }
+
+    let wide = LoadModel {
+        nominal: 100.0,
+        variation: f32::MAX,
+    };
+    assert!(wide.sample(true, &mut rng) >= 0.0);
+}
+
+#[test]
+fn test_socket_draws_nothing_when_off() {
+    let mut socket = Socket::new("heater", 2000.0);
+
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(socket.energy_kwh(), 0.0);
+
+    socket.turn_on();
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(socket.energy_kwh() > 0.0);
+
+    socket.reset_energy();
+    socket.turn_off();
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(socket.energy_kwh() < 1e-6);
+}
+
+#[test]
+fn test_polling_keeps_sampled_load() {
+    let mut socket = Socket::new("heater", 2000.0);
+    socket.turn_on();
+
+    let power = socket.current_power();
+    let energy = socket.energy_kwh();
+
+    for _ in 0..10 {
+        assert_eq!(socket.current_power(), power);
+    }
+    assert!(socket.energy_kwh() >= energy);
+
+    std::thread::sleep(Duration::from_millis(20));
+    socket.sample_meter();
+
+    // Accounted with the load sampled at turn on, up to the new sample
+    let expected = power.value as f64 * 0.02 / 3600.0 / 1000.0;
+    assert!(socket.energy_kwh() >= expected);
+}
+
+#[tokio::test]
+async fn test_energy_commands() -> Result<(), ConnectionError> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let registry = SocketRegistry::new(vec![Socket::new("heater", 2000.0)]).unwrap();
+
+    tokio::spawn(server::serve(
+        listener,
+        registry,
+        ServerConfig::default(),
+        std::future::pending(),
+    ));
+
+    let socket_client = Client::new(&address);
+
+    assert_eq!(socket_client.get_energy("heater").await?, 0.0);
+
+    socket_client.turn_on("heater").await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let consumed = socket_client.get_energy("heater").await?;
+    assert!(consumed > 0.0);
+
+    socket_client.turn_off("heater").await?;
+    socket_client.reset_energy("heater").await?;
+
+    assert_eq!(socket_client.get_energy("heater").await?, 0.0);
+
+    Ok(())
+}
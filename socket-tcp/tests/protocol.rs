@@ -0,0 +1,158 @@
+use socket_tcp::{
+    client::{Client, ConnectionError},
+    protocol::{self, Protocol, PROTOCOL_VERSION},
+    registry::SocketRegistry,
+    response::{ErrorCode, SocketStatus},
+    server::{self, ServerConfig},
+    socket::Socket,
+};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+async fn start_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let registry = SocketRegistry::new(vec![Socket::new("my socket", 20.0)]).unwrap();
+
+    tokio::spawn(server::serve(
+        listener,
+        registry,
+        ServerConfig::default(),
+        std::future::pending(),
+    ));
+
+    address
+}
+
+#[tokio::test]
+async fn test_persistent_framed_connection() -> Result<(), ConnectionError> {
+    let address = start_server().await;
+
+    let socket_client = Client::new(&address);
+
+    assert_eq!(
+        socket_client.protocol().await?,
+        Protocol::Framed(PROTOCOL_VERSION)
+    );
+
+    for _ in 0..3 {
+        assert_eq!(socket_client.turn_on("my socket").await?, SocketStatus::On);
+        assert_eq!(
+            socket_client.get_status("my socket").await?,
+            SocketStatus::On
+        );
+        assert_eq!(
+            socket_client.turn_off("my socket").await?,
+            SocketStatus::Off
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_legacy_text_client() {
+    let address = start_server().await;
+
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    stream.write_all(b"turn_on\n").await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert_eq!(response, "my socket is on");
+}
+
+#[tokio::test]
+async fn test_version_1_client_gets_readable_replies() {
+    let address = start_server().await;
+
+    let mut stream = BufReader::new(TcpStream::connect(&address).await.unwrap());
+    stream
+        .write_all(protocol::handshake_line(1).as_bytes())
+        .await
+        .unwrap();
+
+    let mut handshake = String::new();
+    stream.read_line(&mut handshake).await.unwrap();
+
+    assert_eq!(protocol::parse_handshake(&handshake), Some(1));
+
+    protocol::write_frame(&mut stream, b"turn_on")
+        .await
+        .unwrap();
+    let reply = protocol::read_frame(&mut stream).await.unwrap().unwrap();
+
+    assert_eq!(reply, b"my socket is on");
+}
+
+#[tokio::test]
+async fn test_fallback_to_legacy_server() -> Result<(), ConnectionError> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    // Behaves like a server without framing support: one line, one reply, close
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut request = String::new();
+
+            stream.read_line(&mut request).await.unwrap();
+
+            let reply: &[u8] = match request.trim() {
+                "get_status" => b"my socket is off",
+                "get_status toaster" => b"Cannot find socket with name \"toaster\"",
+                "turn_on" => b"Not allowed to run \"turn_on\"",
+                _ => b"Something went wrong",
+            };
+            stream.write_all(reply).await.unwrap();
+        }
+    });
+
+    let socket_client = Client::new(&address);
+
+    assert_eq!(socket_client.protocol().await?, Protocol::Text);
+    assert_eq!(
+        socket_client.get_status("my socket").await?,
+        SocketStatus::Off
+    );
+    assert!(matches!(
+        socket_client.get_status("toaster").await,
+        Err(ConnectionError::ServerError(ErrorCode::SocketNotFound, _))
+    ));
+    assert!(matches!(
+        socket_client.turn_on("my socket").await,
+        Err(ConnectionError::Unauthorized)
+    ));
+    assert!(matches!(
+        socket_client.turn_off("my socket").await,
+        Err(ConnectionError::MalformedResponse)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_frame_longer_than_legacy_buffer() {
+    let (mut writer, mut reader) = tokio::io::duplex(4096);
+    let payload = "a".repeat(1000);
+
+    protocol::write_frame(&mut writer, payload.as_bytes())
+        .await
+        .unwrap();
+    drop(writer);
+
+    let frame = protocol::read_frame(&mut reader).await.unwrap();
+
+    assert_eq!(frame, Some(payload.into_bytes()));
+    assert_eq!(protocol::read_frame(&mut reader).await.unwrap(), None);
+}
+
+#[test]
+fn test_parse_handshake() {
+    assert_eq!(protocol::parse_handshake("hello 1\n"), Some(1));
+    assert_eq!(protocol::parse_handshake("turn_on\n"), None);
+    assert_eq!(protocol::parse_handshake("hello world\n"), None);
+}
@@ -0,0 +1,147 @@
+use socket_tcp::{
+    client::{Client, ConnectionError},
+    command::Command,
+    registry::{RegistryError, SocketRegistry},
+    response::{ErrorCode, Power, Response, SocketStatus},
+    server::{self, ServerConfig},
+    socket::Socket,
+};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn test_multiple_named_sockets() -> Result<(), ConnectionError> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let registry = SocketRegistry::new(vec![
+        Socket::new("lamp", 60.0),
+        Socket::new("fridge", 150.0),
+    ])
+    .unwrap();
+
+    tokio::spawn(server::serve(
+        listener,
+        registry,
+        ServerConfig::default(),
+        std::future::pending(),
+    ));
+
+    let socket_client = Client::new(&address);
+
+    assert_eq!(socket_client.list_sockets().await?, vec!["fridge", "lamp"]);
+
+    assert_eq!(socket_client.turn_on("lamp").await?, SocketStatus::On);
+    assert_eq!(socket_client.get_status("fridge").await?, SocketStatus::Off);
+    assert_eq!(
+        socket_client.get_power_consumption("fridge").await?,
+        Power::watts(0.0)
+    );
+
+    socket_client.add_socket("kettle", 2000.0).await?;
+
+    let result = socket_client.add_socket("kettle", 2000.0).await;
+    assert!(matches!(
+        result,
+        Err(ConnectionError::ServerError(
+            ErrorCode::SocketAlreadyExists,
+            _
+        ))
+    ));
+    assert_eq!(
+        socket_client.list_sockets().await?,
+        vec!["fridge", "kettle", "lamp"]
+    );
+
+    socket_client.remove_socket("lamp").await?;
+
+    let result = socket_client.turn_on("lamp").await;
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Server responded with error SocketNotFound: Cannot find socket with name \"lamp\""
+    );
+    assert_eq!(
+        socket_client.list_sockets().await?,
+        vec!["fridge", "kettle"]
+    );
+
+    // Longer than the fixed 64-byte buffer the client used to read into
+    for idx in 0..20 {
+        socket_client
+            .add_socket(&format!("lab outlet #{}", idx), 10.0)
+            .await?;
+    }
+    assert_eq!(socket_client.list_sockets().await?.len(), 22);
+
+    Ok(())
+}
+
+#[test]
+fn test_registry_rejects_duplicates() {
+    let result = SocketRegistry::new(vec![Socket::new("lamp", 60.0), Socket::new("lamp", 40.0)]);
+
+    assert_eq!(
+        result.err(),
+        Some(RegistryError::SocketAlreadyExists("lamp".to_string()))
+    );
+}
+
+#[test]
+fn test_registry_is_shared_between_clones() {
+    let registry = SocketRegistry::default();
+    let server_side = registry.clone();
+
+    registry.add(Socket::new("lamp", 60.0)).unwrap();
+    server_side
+        .with_socket("lamp", |socket| socket.turn_on())
+        .unwrap();
+
+    assert_eq!(
+        registry.with_socket("lamp", |socket| socket.get_status()),
+        Ok("lamp is on".to_string())
+    );
+    assert_eq!(
+        registry.remove("fridge").err(),
+        Some(RegistryError::SocketNotFound("fridge".to_string()))
+    );
+}
+
+#[test]
+fn test_response_encoding_roundtrip() {
+    let responses = vec![
+        Response::Status {
+            name: "lamp".to_string(),
+            status: SocketStatus::On,
+        },
+        Response::PowerConsumption {
+            name: "kettle".to_string(),
+            power: Power::watts(1999.5),
+        },
+        Response::Sockets(vec!["my socket".to_string(), "lamp".to_string()]),
+        Response::Sockets(Vec::new()),
+        Response::PowerThresholdSet {
+            name: "kettle".to_string(),
+            watts: Some(1500.0),
+        },
+        Response::PowerThresholdSet {
+            name: "kettle".to_string(),
+            watts: None,
+        },
+        Response::Error {
+            code: ErrorCode::SocketNotFound,
+            message: "Cannot find socket with name \"tv\"".to_string(),
+        },
+    ];
+
+    for response in responses {
+        assert_eq!(Response::decode(&response.encode()), Some(response));
+    }
+
+    assert_eq!(Response::decode("lamp is on"), None);
+}
+
+#[test]
+fn test_names_with_tabs_are_rejected() {
+    assert!("turn_on lamp\tkitchen".parse::<Command>().is_err());
+    assert!("add_socket 60 lamp\t".parse::<Command>().is_err());
+    assert!("turn_on kitchen lamp".parse::<Command>().is_ok());
+}
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use socket_tcp::{
+    client::{Client, ConnectionError},
+    registry::{RegistryError, SocketRegistry},
+    response::{ErrorCode, SocketStatus},
+    schedule::{MockClock, ScheduleAction, Trigger},
+    server,
+    socket::Socket,
+};
+use tokio::net::TcpListener;
+
+// 2021-01-01 10:00:00 UTC
+fn morning() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_609_495_200)
+}
+
+fn registry_with_clock(clock: Arc<MockClock>) -> SocketRegistry {
+    SocketRegistry::new(vec![Socket::new("lamp", 20.0)])
+        .unwrap()
+        .with_clock(clock)
+}
+
+fn is_on(registry: &SocketRegistry) -> bool {
+    registry
+        .with_socket("lamp", |socket| socket.status)
+        .unwrap()
+}
+
+#[test]
+fn test_countdown_fires_once() {
+    let clock = Arc::new(MockClock::new(morning()));
+    let registry = registry_with_clock(clock.clone());
+
+    registry
+        .add_schedule(
+            "lamp",
+            ScheduleAction::TurnOn,
+            Trigger::After(Duration::from_secs(60)),
+        )
+        .unwrap();
+
+    clock.advance(Duration::from_secs(59));
+    assert_eq!(registry.run_due_schedules(), 0);
+    assert!(!is_on(&registry));
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(registry.run_due_schedules(), 1);
+    assert!(is_on(&registry));
+    assert!(registry.schedules("lamp").unwrap().is_empty());
+}
+
+#[test]
+fn test_daily_schedule_repeats() {
+    let clock = Arc::new(MockClock::new(morning()));
+    let registry = registry_with_clock(clock.clone());
+    let trigger = Trigger::daily_at(22, 30).unwrap();
+
+    let schedule = registry
+        .add_schedule("lamp", ScheduleAction::TurnOn, trigger)
+        .unwrap();
+
+    assert_eq!(
+        schedule.next_run,
+        morning() + Duration::from_secs(12 * 3600 + 30 * 60)
+    );
+
+    clock.advance(Duration::from_secs(13 * 3600));
+    assert_eq!(registry.run_due_schedules(), 1);
+    assert!(is_on(&registry));
+
+    let schedules = registry.schedules("lamp").unwrap();
+    assert_eq!(schedules.len(), 1);
+    assert_eq!(
+        schedules[0].next_run,
+        schedule.next_run + Duration::from_secs(24 * 3600)
+    );
+}
+
+#[test]
+fn test_cancel_schedule() {
+    let clock = Arc::new(MockClock::new(morning()));
+    let registry = registry_with_clock(clock.clone());
+
+    let schedule = registry
+        .add_schedule(
+            "lamp",
+            ScheduleAction::TurnOn,
+            Trigger::After(Duration::from_secs(60)),
+        )
+        .unwrap();
+
+    assert_eq!(
+        registry.cancel_schedule("lamp", schedule.id),
+        Ok(schedule.clone())
+    );
+    assert_eq!(
+        registry.cancel_schedule("lamp", schedule.id),
+        Err(RegistryError::ScheduleNotFound(schedule.id))
+    );
+
+    clock.advance(Duration::from_secs(120));
+    assert_eq!(registry.run_due_schedules(), 0);
+    assert!(!is_on(&registry));
+}
+
+#[test]
+fn test_schedule_out_of_range() {
+    let registry = registry_with_clock(Arc::new(MockClock::new(morning())));
+    let trigger = Trigger::After(Duration::MAX);
+
+    assert_eq!(
+        registry.add_schedule("lamp", ScheduleAction::TurnOn, trigger),
+        Err(RegistryError::ScheduleOutOfRange(trigger))
+    );
+    assert!(registry.schedules("lamp").unwrap().is_empty());
+}
+
+#[test]
+fn test_trigger_parsing() {
+    assert_eq!("in:90".parse(), Ok(Trigger::After(Duration::from_secs(90))));
+    assert_eq!("daily:07:05".parse(), Ok(Trigger::daily_at(7, 5).unwrap()));
+    assert_eq!("daily:24:00".parse::<Trigger>(), Err(()));
+    assert_eq!("in:18446744073709551615".parse::<Trigger>(), Err(()));
+    assert_eq!(Trigger::daily_at(7, 5).unwrap().to_string(), "daily:07:05");
+}
+
+#[tokio::test]
+async fn test_schedules_over_client() -> Result<(), ConnectionError> {
+    let clock = Arc::new(MockClock::new(morning()));
+    let registry = registry_with_clock(clock.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let config = server::ServerConfig {
+        schedule_interval: Duration::from_millis(10),
+        ..Default::default()
+    };
+
+    tokio::spawn(server::serve(
+        listener,
+        registry,
+        config,
+        std::future::pending(),
+    ));
+
+    let socket_client = Client::new(&address);
+
+    let schedule = socket_client
+        .add_schedule(
+            "lamp",
+            ScheduleAction::TurnOn,
+            Trigger::After(Duration::from_secs(60)),
+        )
+        .await?;
+    let cancelled = socket_client
+        .add_schedule(
+            "lamp",
+            ScheduleAction::TurnOff,
+            Trigger::daily_at(23, 0).unwrap(),
+        )
+        .await?;
+
+    assert_eq!(
+        socket_client.list_schedules("lamp").await?,
+        vec![schedule.clone(), cancelled.clone()]
+    );
+
+    socket_client.cancel_schedule("lamp", cancelled.id).await?;
+
+    assert!(matches!(
+        socket_client.cancel_schedule("lamp", cancelled.id).await,
+        Err(ConnectionError::ServerError(ErrorCode::ScheduleNotFound, _))
+    ));
+
+    // An oversized delay is refused and leaves the server answering
+    let oversized = Trigger::After(Duration::from_secs(u64::MAX));
+    assert!(matches!(
+        socket_client
+            .add_schedule("lamp", ScheduleAction::TurnOn, oversized)
+            .await,
+        Err(ConnectionError::ServerError(ErrorCode::UnknownCommand, _))
+    ));
+    assert_eq!(
+        Client::new(&address).list_schedules("lamp").await?,
+        vec![schedule.clone()]
+    );
+
+    clock.advance(Duration::from_secs(60));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(socket_client.get_status("lamp").await?, SocketStatus::On);
+    assert!(socket_client.list_schedules("lamp").await?.is_empty());
+
+    Ok(())
+}
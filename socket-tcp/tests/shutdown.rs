@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use socket_tcp::{
+    client::{Client, ConnectionError},
+    registry::SocketRegistry,
+    response::SocketStatus,
+    server::{self, ServerConfig},
+    socket::Socket,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+async fn start_server(
+    config: ServerConfig,
+) -> (String, oneshot::Sender<()>, JoinHandle<std::io::Result<()>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let (stop_sender, stop_receiver) = oneshot::channel::<()>();
+
+    let registry = SocketRegistry::new(vec![Socket::new("my socket", 20.0)]).unwrap();
+
+    let shutdown = async {
+        let _r = stop_receiver.await;
+    };
+    let handle = tokio::spawn(server::serve(listener, registry, config, shutdown));
+
+    (address, stop_sender, handle)
+}
+
+#[tokio::test]
+async fn test_shutdown_closes_connections_and_frees_port() -> Result<(), ConnectionError> {
+    let (address, stop, handle) = start_server(ServerConfig::default()).await;
+
+    let socket_client = Client::new(&address);
+    assert_eq!(socket_client.turn_on("my socket").await?, SocketStatus::On);
+
+    stop.send(()).unwrap();
+
+    // The idle persistent connection must not keep the server alive
+    let result = timeout(Duration::from_secs(1), handle).await;
+    assert!(matches!(result, Ok(Ok(Ok(())))));
+
+    let result = Client::new(&address).get_status("my socket").await;
+    assert!(matches!(result, Err(ConnectionError::CantConnect(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_connections() -> Result<(), ConnectionError> {
+    let config = ServerConfig {
+        max_connections: 1,
+        ..ServerConfig::default()
+    };
+    let (address, _stop, _handle) = start_server(config).await;
+
+    let first_client = Client::new(&address);
+    first_client.get_status("my socket").await?;
+
+    let second_client = Client::new(&address);
+    let waiting = timeout(
+        Duration::from_millis(200),
+        second_client.get_status("my socket"),
+    )
+    .await;
+    assert!(waiting.is_err());
+
+    drop(first_client);
+
+    let result = timeout(
+        Duration::from_secs(1),
+        second_client.get_status("my socket"),
+    )
+    .await;
+    assert!(matches!(result, Ok(Ok(SocketStatus::Off))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_reconnects_after_idle_timeout() -> Result<(), ConnectionError> {
+    let config = ServerConfig {
+        idle_timeout: Duration::from_millis(100),
+        ..ServerConfig::default()
+    };
+    let (address, _stop, _handle) = start_server(config).await;
+
+    let socket_client = Client::new(&address);
+    socket_client.turn_on("my socket").await?;
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // The server closed the idle connection, the command is sent again on
+    // a new one
+    assert_eq!(
+        socket_client.get_status("my socket").await?,
+        SocketStatus::On
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_command_reply() {
+    let (address, _stop, _handle) = start_server(ServerConfig::default()).await;
+
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    stream.write_all(b"dance\n").await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert_eq!(response, "Unknown command \"dance\"");
+
+    // The server keeps serving after a bad request
+    let socket_client = Client::new(&address);
+    assert_eq!(
+        socket_client.get_status("my socket").await.unwrap(),
+        SocketStatus::Off
+    );
+}
@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use socket_tcp::{
+    client::{Client, ConnectionError, EventStream},
+    event::Event,
+    registry::SocketRegistry,
+    response::ErrorCode,
+    server::{self, ServerConfig},
+    socket::Socket,
+};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+
+async fn start_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let registry = SocketRegistry::new(vec![
+        Socket::new("lamp", 60.0),
+        Socket::new("kettle", 2000.0).with_power_threshold(1000.0),
+    ])
+    .unwrap();
+
+    tokio::spawn(server::serve(
+        listener,
+        registry,
+        ServerConfig::default(),
+        std::future::pending(),
+    ));
+
+    address
+}
+
+async fn next(events: &mut EventStream) -> Result<Event, ConnectionError> {
+    timeout(Duration::from_secs(1), events.next())
+        .await
+        .expect("Event should arrive")
+        .expect("Subscription should stay open")
+}
+
+#[test]
+fn test_registry_publishes_changes() {
+    let registry = SocketRegistry::new(vec![
+        Socket::new("kettle", 2000.0).with_power_threshold(1000.0)
+    ])
+    .unwrap();
+    let mut events = registry.subscribe();
+
+    registry
+        .with_socket("kettle", |socket| socket.turn_on())
+        .unwrap();
+    registry
+        .with_socket("kettle", |socket| socket.turn_on())
+        .unwrap();
+    registry
+        .with_socket("kettle", |socket| socket.turn_off())
+        .unwrap();
+
+    assert_eq!(
+        events.try_recv().unwrap(),
+        Event::TurnedOn("kettle".to_string())
+    );
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        Event::PowerThresholdCrossed { above: true, .. }
+    ));
+    assert_eq!(
+        events.try_recv().unwrap(),
+        Event::TurnedOff("kettle".to_string())
+    );
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        Event::PowerThresholdCrossed { above: false, .. }
+    ));
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_subscriber_receives_changes_of_other_clients() -> Result<(), ConnectionError> {
+    let address = start_server().await;
+
+    let socket_client = Client::new(&address);
+    let mut all_events = socket_client.subscribe(None).await?;
+    let mut lamp_events = socket_client.subscribe(Some("lamp")).await?;
+
+    let other_client = Client::new(&address);
+    other_client.turn_on("kettle").await?;
+    other_client.turn_on("lamp").await?;
+
+    assert_eq!(
+        next(&mut all_events).await?,
+        Event::TurnedOn("kettle".to_string())
+    );
+    assert!(matches!(
+        next(&mut all_events).await?,
+        Event::PowerThresholdCrossed { above: true, name, .. } if name == "kettle"
+    ));
+    assert_eq!(
+        next(&mut all_events).await?,
+        Event::TurnedOn("lamp".to_string())
+    );
+    assert_eq!(
+        next(&mut lamp_events).await?,
+        Event::TurnedOn("lamp".to_string())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscribe_to_unknown_socket() {
+    let address = start_server().await;
+
+    let socket_client = Client::new(&address);
+
+    assert!(matches!(
+        socket_client.subscribe(Some("toaster")).await,
+        Err(ConnectionError::ServerError(ErrorCode::SocketNotFound, _))
+    ));
+}
+
+#[test]
+fn test_meter_samples_publish_threshold_crossings() {
+    let mut fridge = Socket::new("fridge", 100.0).with_power_threshold(100.0);
+    fridge.load.variation = 0.5;
+    fridge.turn_on();
+
+    let registry = SocketRegistry::new(vec![fridge]).unwrap();
+    let mut events = registry.subscribe();
+    let mut crossings = Vec::new();
+
+    for _ in 0..100 {
+        registry.sample_meters();
+
+        while let Ok(Event::PowerThresholdCrossed { above, .. }) = events.try_recv() {
+            crossings.push(above);
+        }
+    }
+
+    assert!(crossings.contains(&true) && crossings.contains(&false));
+    assert!(crossings.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[tokio::test]
+async fn test_set_power_threshold() -> Result<(), ConnectionError> {
+    let address = start_server().await;
+
+    let socket_client = Client::new(&address);
+    socket_client.turn_on("lamp").await?;
+
+    let mut events = socket_client.subscribe(Some("lamp")).await?;
+    let other_client = Client::new(&address);
+
+    other_client.set_power_threshold("lamp", Some(30.0)).await?;
+
+    assert!(matches!(
+        next(&mut events).await?,
+        Event::PowerThresholdCrossed { above: true, .. }
+    ));
+
+    // Removing the threshold crosses nothing, the next event is the turn off
+    other_client.set_power_threshold("lamp", None).await?;
+    other_client.turn_off("lamp").await?;
+
+    assert_eq!(
+        next(&mut events).await?,
+        Event::TurnedOff("lamp".to_string())
+    );
+    assert!(matches!(
+        other_client
+            .set_power_threshold("toaster", Some(30.0))
+            .await,
+        Err(ConnectionError::ServerError(ErrorCode::SocketNotFound, _))
+    ));
+
+    Ok(())
+}
@@ -1,12 +1,32 @@
 use core::time;
-use std::thread;
 
+use thermometer_udp::sender::Sender;
 use thermometer_udp::thermometer::Thermometer;
+use thermometer_udp::waveform::Sine;
 
 #[tokio::main]
 async fn main() {
     let thermo = Thermometer::new("Thermo#1".to_string(), "127.0.0.1:3334".to_string()).await;
 
+    // Emulates a day/night cycle compressed into two minutes
+    tokio::spawn(async {
+        let mut sender = Sender::new("127.0.0.1:3334", 1)
+            .await
+            .expect("Sender should bind");
+        let mut waveform = Sine {
+            mean: 20.0,
+            amplitude: 5.0,
+            period: time::Duration::from_secs(120),
+        };
+
+        if let Err(e) = sender
+            .run(&mut waveform, time::Duration::from_secs(1), None)
+            .await
+        {
+            eprintln!("{}", e);
+        }
+    });
+
     loop {
         match thermo.as_ref() {
             Ok(thermo) => {
@@ -15,6 +35,6 @@ async fn main() {
             Err(e) => eprintln!("{}", e),
         }
 
-        thread::sleep(time::Duration::from_secs(2));
+        tokio::time::sleep(time::Duration::from_secs(2)).await;
     }
 }
@@ -0,0 +1,199 @@
+use std::time::{Duration, SystemTime};
+
+use crate::history::Reading;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Above(f32),
+    Below(f32),
+    /// Temperature changes faster than this many degrees per minute, either way
+    RateOfChange(f32),
+}
+
+/// Condition that raises an alert once it held for `min_duration`. The alert
+/// is cleared when the reading is `hysteresis` degrees (or degrees per
+/// minute) back on the safe side, so values hovering around a threshold
+/// don't flap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub name: String,
+    pub condition: Condition,
+    pub hysteresis: f32,
+    pub min_duration: Duration,
+}
+
+impl AlertRule {
+    pub fn new(name: &str, condition: Condition) -> Self {
+        Self {
+            name: name.to_string(),
+            condition,
+            hysteresis: 0.0,
+            min_duration: Duration::ZERO,
+        }
+    }
+
+    pub fn above(name: &str, threshold: f32) -> Self {
+        Self::new(name, Condition::Above(threshold))
+    }
+
+    pub fn below(name: &str, threshold: f32) -> Self {
+        Self::new(name, Condition::Below(threshold))
+    }
+
+    pub fn rate_of_change(name: &str, degrees_per_minute: f32) -> Self {
+        Self::new(name, Condition::RateOfChange(degrees_per_minute))
+    }
+
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    pub fn with_min_duration(mut self, min_duration: Duration) -> Self {
+        self.min_duration = min_duration;
+        self
+    }
+
+    // Whether `value` breaks the rule, or still does for an active alert
+    fn is_violated(&self, value: f32, active: bool) -> bool {
+        let margin = match active {
+            true => self.hysteresis,
+            false => 0.0,
+        };
+
+        match self.condition {
+            Condition::Above(threshold) => value > threshold - margin,
+            Condition::Below(threshold) => value < threshold + margin,
+            Condition::RateOfChange(limit) => value.abs() > limit - margin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub thermometer: String,
+    pub rule: String,
+    pub condition: Condition,
+    /// Temperature, or degrees per minute for rate of change rules
+    pub value: f32,
+    pub timestamp: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertEvent {
+    Raised(Alert),
+    Cleared(Alert),
+}
+
+impl AlertEvent {
+    pub fn alert(&self) -> &Alert {
+        match self {
+            AlertEvent::Raised(alert) | AlertEvent::Cleared(alert) => alert,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleState {
+    Normal,
+    /// Violated since the timestamp, but not for long enough yet
+    Pending(SystemTime),
+    Active,
+}
+
+/// Checks every reading of one thermometer against its rules. Durations and
+/// rates are measured with reading timestamps, not with the wall clock.
+#[derive(Debug, Clone)]
+pub struct AlertEvaluator {
+    pub thermometer: String,
+
+    rules: Vec<(AlertRule, RuleState)>,
+    previous: Option<Reading>,
+}
+
+impl AlertEvaluator {
+    pub fn new(thermometer: &str, rules: Vec<AlertRule>) -> Self {
+        Self {
+            thermometer: thermometer.to_string(),
+            rules: rules
+                .into_iter()
+                .map(|rule| (rule, RuleState::Normal))
+                .collect(),
+            previous: None,
+        }
+    }
+
+    /// Events caused by the reading, in rule order
+    pub fn observe(&mut self, reading: Reading) -> Vec<AlertEvent> {
+        let rate = self
+            .previous
+            .and_then(|previous| rate_per_minute(previous, reading));
+        let mut events = Vec::new();
+
+        for (rule, state) in &mut self.rules {
+            let value = match rule.condition {
+                Condition::Above(_) | Condition::Below(_) => reading.temperature,
+                Condition::RateOfChange(_) => match rate {
+                    Some(rate) => rate,
+                    None => continue,
+                },
+            };
+
+            let alert = || Alert {
+                thermometer: self.thermometer.clone(),
+                rule: rule.name.clone(),
+                condition: rule.condition,
+                value,
+                timestamp: reading.timestamp,
+            };
+
+            let violated = rule.is_violated(value, *state == RuleState::Active);
+
+            *state = match (*state, violated) {
+                (RuleState::Active, true) => RuleState::Active,
+                (RuleState::Active, false) => {
+                    events.push(AlertEvent::Cleared(alert()));
+                    RuleState::Normal
+                }
+                (_, false) => RuleState::Normal,
+                (RuleState::Normal, true) => RuleState::Pending(reading.timestamp),
+                (pending, true) => pending,
+            };
+
+            if let RuleState::Pending(since) = *state {
+                let held = reading.timestamp.duration_since(since).unwrap_or_default();
+
+                if held >= rule.min_duration {
+                    events.push(AlertEvent::Raised(alert()));
+                    *state = RuleState::Active;
+                }
+            }
+        }
+
+        if !matches!(self.previous, Some(previous) if previous.timestamp >= reading.timestamp) {
+            self.previous = Some(reading);
+        }
+
+        events
+    }
+
+    /// Names of the rules with a raised alert
+    pub fn active(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|(_, state)| *state == RuleState::Active)
+            .map(|(rule, _)| rule.name.clone())
+            .collect()
+    }
+}
+
+// None for readings that aren't later than the previous one
+fn rate_per_minute(previous: Reading, reading: Reading) -> Option<f32> {
+    let elapsed = reading.timestamp.duration_since(previous.timestamp).ok()?;
+
+    if elapsed.is_zero() {
+        return None;
+    }
+
+    Some((reading.temperature - previous.temperature) / elapsed.as_secs_f32() * 60.0)
+}
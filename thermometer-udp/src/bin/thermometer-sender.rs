@@ -0,0 +1,124 @@
+use std::process::exit;
+use std::time::Duration;
+
+use thermometer_udp::discovery::{
+    Announcement, Announcer, DiscoveryConfig, DEFAULT_ANNOUNCE_INTERVAL,
+};
+use thermometer_udp::sender::Sender;
+use thermometer_udp::waveform::parse_waveform;
+
+const USAGE: &str = "Usage: thermometer-sender <target address> [--device-id <id>] \
+[--interval-ms <ms>] [--count <n>] [--legacy] [--waveform <spec>] \
+[--announce <name>]
+
+Waveforms: constant:<t>, sine:<mean>:<amplitude>[:<period secs>],
+walk:<start>:<step>[:<seed>], csv:<path>
+
+With --announce the sensor advertises its device id and the address it sends
+from for discovery under <name>";
+
+struct Args {
+    target: String,
+    device_id: u32,
+    interval: Duration,
+    count: Option<u64>,
+    legacy: bool,
+    waveform: String,
+    announce: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        target: String::new(),
+        device_id: 1,
+        interval: Duration::from_secs(1),
+        count: None,
+        legacy: false,
+        waveform: "sine:20:5".to_string(),
+        announce: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--device-id" => parsed.device_id = value()?.parse().map_err(|_| "Bad device id")?,
+            "--interval-ms" => {
+                let ms = value()?.parse().map_err(|_| "Bad interval")?;
+                parsed.interval = Duration::from_millis(ms);
+            }
+            "--count" => parsed.count = Some(value()?.parse().map_err(|_| "Bad count")?),
+            "--legacy" => parsed.legacy = true,
+            "--waveform" => parsed.waveform = value()?,
+            "--announce" => parsed.announce = Some(value()?),
+            _ if parsed.target.is_empty() && !arg.starts_with("--") => parsed.target = arg,
+            _ => return Err(format!("Unexpected argument {:?}", arg)),
+        }
+    }
+
+    if parsed.target.is_empty() {
+        return Err("Missing target address".to_string());
+    }
+
+    if parsed.interval.is_zero() {
+        return Err("Interval should be positive".to_string());
+    }
+
+    Ok(parsed)
+}
+
+#[tokio::main]
+async fn main() {
+    let args = parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        exit(2);
+    });
+
+    let mut waveform = parse_waveform(&args.waveform).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        exit(2);
+    });
+
+    let mut sender = match Sender::new(&args.target, args.device_id).await {
+        Ok(sender) => sender,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    sender.legacy = args.legacy;
+
+    let _announcer = match &args.announce {
+        Some(name) => {
+            let address = match sender.local_addr().await {
+                Ok(address) => address.to_string(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            let announcement = Announcement::new(name, args.device_id, &address, &["temperature"]);
+            let config = DiscoveryConfig::default();
+
+            match Announcer::start(announcement, &config, DEFAULT_ANNOUNCE_INTERVAL).await {
+                Ok(announcer) => Some(announcer),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    match sender
+        .run(waveform.as_mut(), args.interval, args.count)
+        .await
+    {
+        Ok(sent) => println!("Sent {} readings to {}", sent, args.target),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+use crate::metric::{Metric, METRICS};
+
+pub const MAGIC: [u8; 2] = *b"TH";
+
+pub const VERSION: u8 = 1;
+
+/// Version of datagrams carrying several metrics
+pub const SENSOR_VERSION: u8 = 2;
+
+/// magic (2) + version (1) + device id (4) + sequence (8) + timestamp (8)
+/// + temperature (4) + checksum (4), all integers big-endian
+pub const DATAGRAM_SIZE: usize = 31;
+
+/// Bare big-endian `f32` sent by old thermometers
+pub const LEGACY_DATAGRAM_SIZE: usize = 4;
+
+const CHECKSUM_OFFSET: usize = DATAGRAM_SIZE - 4;
+
+/// Sensor datagram without metrics: the datagram header up to the timestamp,
+/// metric count (1) and checksum (4). Every metric adds id (1) + value (4).
+const SENSOR_HEADER_SIZE: usize = 28;
+const METRICS_OFFSET: usize = 24;
+const METRIC_SIZE: usize = 5;
+
+pub const MAX_SENSOR_DATAGRAM_SIZE: usize = SENSOR_HEADER_SIZE + METRICS.len() * METRIC_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Datagram {
+    pub device_id: u32,
+    /// Increases by one with every datagram a device sends
+    pub sequence: u64,
+    /// Moment the temperature was measured, millisecond precision
+    pub timestamp: SystemTime,
+    pub temperature: f32,
+}
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum DatagramError {
+    #[error("Datagram has wrong size: {:?}", .0)]
+    WrongSize(usize),
+    #[error("Datagram doesn't start with the thermometer magic")]
+    BadMagic,
+    #[error("Unsupported datagram version: {:?}", .0)]
+    UnsupportedVersion(u8),
+    #[error("Datagram checksum doesn't match its content")]
+    ChecksumMismatch,
+    #[error("Unknown metric id: {:?}", .0)]
+    UnknownMetric(u8),
+    #[error("Metric is repeated in the datagram: {:?}", .0)]
+    DuplicateMetric(u8),
+}
+
+/// Reading of several metrics of an environmental sensor
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorDatagram {
+    pub device_id: u32,
+    pub sequence: u64,
+    pub timestamp: SystemTime,
+    /// Every metric at most once
+    pub metrics: Vec<(Metric, f32)>,
+}
+
+impl Datagram {
+    /// Reading measured right now
+    pub fn new(device_id: u32, sequence: u64, temperature: f32) -> Self {
+        Self {
+            device_id,
+            sequence,
+            timestamp: SystemTime::now(),
+            temperature,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; DATAGRAM_SIZE] {
+        let timestamp = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut bytes = [0; DATAGRAM_SIZE];
+        bytes[0..2].copy_from_slice(&MAGIC);
+        bytes[2] = VERSION;
+        bytes[3..7].copy_from_slice(&self.device_id.to_be_bytes());
+        bytes[7..15].copy_from_slice(&self.sequence.to_be_bytes());
+        bytes[15..23].copy_from_slice(&timestamp.to_be_bytes());
+        bytes[23..27].copy_from_slice(&self.temperature.to_be_bytes());
+
+        let checksum = crc32fast::hash(&bytes[..CHECKSUM_OFFSET]);
+        bytes[CHECKSUM_OFFSET..].copy_from_slice(&checksum.to_be_bytes());
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DatagramError> {
+        if bytes.len() != DATAGRAM_SIZE {
+            return Err(DatagramError::WrongSize(bytes.len()));
+        }
+
+        if bytes[0..2] != MAGIC {
+            return Err(DatagramError::BadMagic);
+        }
+
+        if bytes[2] != VERSION {
+            return Err(DatagramError::UnsupportedVersion(bytes[2]));
+        }
+
+        let checksum = u32::from_be_bytes(field(bytes, CHECKSUM_OFFSET));
+
+        if crc32fast::hash(&bytes[..CHECKSUM_OFFSET]) != checksum {
+            return Err(DatagramError::ChecksumMismatch);
+        }
+
+        let timestamp = u64::from_be_bytes(field(bytes, 15));
+
+        Ok(Self {
+            device_id: u32::from_be_bytes(field(bytes, 3)),
+            sequence: u64::from_be_bytes(field(bytes, 7)),
+            timestamp: UNIX_EPOCH + Duration::from_millis(timestamp),
+            temperature: f32::from_be_bytes(field(bytes, 23)),
+        })
+    }
+}
+
+impl SensorDatagram {
+    pub fn new(device_id: u32, sequence: u64, metrics: &[(Metric, f32)]) -> Self {
+        let mut unique: Vec<(Metric, f32)> = Vec::new();
+
+        for (metric, value) in metrics {
+            match unique.iter_mut().find(|(known, _)| known == metric) {
+                Some(known) => known.1 = *value,
+                None => unique.push((*metric, *value)),
+            }
+        }
+
+        Self {
+            device_id,
+            sequence,
+            timestamp: SystemTime::now(),
+            metrics: unique,
+        }
+    }
+
+    pub fn value(&self, metric: Metric) -> Option<f32> {
+        self.metrics
+            .iter()
+            .find(|(known, _)| *known == metric)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let timestamp = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut bytes = Vec::with_capacity(MAX_SENSOR_DATAGRAM_SIZE);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(SENSOR_VERSION);
+        bytes.extend_from_slice(&self.device_id.to_be_bytes());
+        bytes.extend_from_slice(&self.sequence.to_be_bytes());
+        bytes.extend_from_slice(&timestamp.to_be_bytes());
+        bytes.push(self.metrics.len() as u8);
+
+        for (metric, value) in &self.metrics {
+            bytes.push(metric.id());
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DatagramError> {
+        if bytes.len() < SENSOR_HEADER_SIZE || bytes.len() > MAX_SENSOR_DATAGRAM_SIZE {
+            return Err(DatagramError::WrongSize(bytes.len()));
+        }
+
+        if bytes[0..2] != MAGIC {
+            return Err(DatagramError::BadMagic);
+        }
+
+        if bytes[2] != SENSOR_VERSION {
+            return Err(DatagramError::UnsupportedVersion(bytes[2]));
+        }
+
+        let count = bytes[METRICS_OFFSET - 1] as usize;
+
+        if bytes.len() != SENSOR_HEADER_SIZE + count * METRIC_SIZE {
+            return Err(DatagramError::WrongSize(bytes.len()));
+        }
+
+        let checksum_offset = bytes.len() - 4;
+        let checksum = u32::from_be_bytes(field(bytes, checksum_offset));
+
+        if crc32fast::hash(&bytes[..checksum_offset]) != checksum {
+            return Err(DatagramError::ChecksumMismatch);
+        }
+
+        let mut metrics: Vec<(Metric, f32)> = Vec::with_capacity(count);
+
+        for chunk in bytes[METRICS_OFFSET..checksum_offset].chunks(METRIC_SIZE) {
+            let metric = Metric::from_id(chunk[0]).ok_or(DatagramError::UnknownMetric(chunk[0]))?;
+
+            if metrics.iter().any(|(known, _)| *known == metric) {
+                return Err(DatagramError::DuplicateMetric(chunk[0]));
+            }
+
+            metrics.push((metric, f32::from_be_bytes(field(chunk, 1))));
+        }
+
+        let timestamp = u64::from_be_bytes(field(bytes, 15));
+
+        Ok(Self {
+            device_id: u32::from_be_bytes(field(bytes, 3)),
+            sequence: u64::from_be_bytes(field(bytes, 7)),
+            timestamp: UNIX_EPOCH + Duration::from_millis(timestamp),
+            metrics,
+        })
+    }
+}
+
+impl From<Datagram> for SensorDatagram {
+    fn from(datagram: Datagram) -> Self {
+        Self {
+            device_id: datagram.device_id,
+            sequence: datagram.sequence,
+            timestamp: datagram.timestamp,
+            metrics: vec![(Metric::Temperature, datagram.temperature)],
+        }
+    }
+}
+
+fn field<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+    bytes[offset..offset + N]
+        .try_into()
+        .expect("Datagram size is checked before reading fields")
+}
+
+/// Datagram of either version, thermometer ones as temperature only readings
+pub fn decode_reading(bytes: &[u8]) -> Result<SensorDatagram, DatagramError> {
+    match bytes.get(2) {
+        Some(&VERSION) => Datagram::decode(bytes).map(SensorDatagram::from),
+        _ => SensorDatagram::decode(bytes),
+    }
+}
+
+pub fn decode_legacy(bytes: &[u8]) -> Result<f32, DatagramError> {
+    let bytes: [u8; LEGACY_DATAGRAM_SIZE] = bytes
+        .try_into()
+        .map_err(|_| DatagramError::WrongSize(bytes.len()))?;
+
+    Ok(f32::from_be_bytes(bytes))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceCheck {
+    New,
+    Duplicate,
+    OutOfOrder,
+}
+
+/// Remembers the last datagram accepted from every device
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    last_seen: HashMap<u32, (u64, SystemTime)>,
+}
+
+impl SequenceTracker {
+    /// Records the datagram if it is newer than everything seen from its
+    /// device. A lower sequence with a later timestamp means the device
+    /// restarted and counts as new.
+    pub fn check(&mut self, datagram: &Datagram) -> SequenceCheck {
+        self.check_sequence(datagram.device_id, datagram.sequence, datagram.timestamp)
+    }
+
+    pub fn check_sensor(&mut self, datagram: &SensorDatagram) -> SequenceCheck {
+        self.check_sequence(datagram.device_id, datagram.sequence, datagram.timestamp)
+    }
+
+    /// Takes the next datagram of the device as new, whatever its sequence
+    pub fn forget(&mut self, device_id: u32) {
+        self.last_seen.remove(&device_id);
+    }
+
+    fn check_sequence(
+        &mut self,
+        device_id: u32,
+        sequence: u64,
+        timestamp: SystemTime,
+    ) -> SequenceCheck {
+        let result = match self.last_seen.get(&device_id) {
+            None => SequenceCheck::New,
+            Some((last, _)) if sequence > *last => SequenceCheck::New,
+            Some((last, _)) if sequence == *last => SequenceCheck::Duplicate,
+            Some((_, last)) if timestamp > *last => SequenceCheck::New,
+            Some(_) => SequenceCheck::OutOfOrder,
+        };
+
+        if result == SequenceCheck::New {
+            self.last_seen.insert(device_id, (sequence, timestamp));
+        }
+
+        result
+    }
+}
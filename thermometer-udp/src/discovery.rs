@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use socket2::{Domain, Protocol, Socket, Type};
+use thiserror::Error;
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+pub const ANNOUNCEMENT_MAGIC: [u8; 2] = *b"TA";
+
+pub const ANNOUNCEMENT_VERSION: u8 = 2;
+
+/// Kept apart from the ports thermometers receive readings on
+pub const DISCOVERY_PORT: u16 = 3390;
+
+pub const DEFAULT_GROUP: SocketAddrV4 =
+    SocketAddrV4::new(Ipv4Addr::new(239, 255, 33, 34), DISCOVERY_PORT);
+
+pub const DEFAULT_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Announcements are small, anything longer is not one of ours
+const MAX_ANNOUNCEMENT_SIZE: usize = 1024;
+
+/// What a sensor tells about itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    pub name: String,
+    /// Device id of the sensor's datagrams
+    pub device_id: u32,
+    /// Address the sensor sends its readings from
+    pub address: String,
+    /// Measured quantities and features, like "temperature"
+    pub capabilities: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    #[error("Failed to bind discovery socket: {:?}", .0)]
+    BindError(String),
+    #[error("Failed to send announcement: {:?}", .0)]
+    SendError(String),
+    #[error("Malformed announcement: {:?}", .0)]
+    BadAnnouncement(String),
+}
+
+impl Announcement {
+    pub fn new(name: &str, device_id: u32, address: &str, capabilities: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            device_id,
+            address: address.to_string(),
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Magic and version followed by name, device id, address and comma
+    /// separated capabilities, one per line
+    pub fn encode(&self) -> Result<Vec<u8>, DiscoveryError> {
+        let fields = [&self.name, &self.address];
+
+        if fields
+            .iter()
+            .any(|field| field.is_empty() || field.contains('\n'))
+            || self
+                .capabilities
+                .iter()
+                .any(|c| c.is_empty() || c.contains([',', '\n']))
+        {
+            return Err(DiscoveryError::BadAnnouncement(format!("{:?}", self)));
+        }
+
+        let mut bytes = ANNOUNCEMENT_MAGIC.to_vec();
+        bytes.push(ANNOUNCEMENT_VERSION);
+        bytes.extend_from_slice(
+            format!(
+                "{}\n{}\n{}\n{}",
+                self.name,
+                self.device_id,
+                self.address,
+                self.capabilities.join(",")
+            )
+            .as_bytes(),
+        );
+
+        if bytes.len() > MAX_ANNOUNCEMENT_SIZE {
+            return Err(DiscoveryError::BadAnnouncement(format!("{:?}", self)));
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DiscoveryError> {
+        let bad = || DiscoveryError::BadAnnouncement(String::from_utf8_lossy(bytes).to_string());
+
+        if bytes.len() < 3 || bytes[0..2] != ANNOUNCEMENT_MAGIC {
+            return Err(bad());
+        }
+
+        if bytes[2] != ANNOUNCEMENT_VERSION {
+            return Err(bad());
+        }
+
+        let text = std::str::from_utf8(&bytes[3..]).map_err(|_| bad())?;
+
+        match text.split('\n').collect::<Vec<_>>()[..] {
+            [name, device_id, address, capabilities] if !name.is_empty() && !address.is_empty() => {
+                Ok(Self {
+                    name: name.to_string(),
+                    device_id: device_id.parse().map_err(|_| bad())?,
+                    address: address.to_string(),
+                    capabilities: capabilities
+                        .split(',')
+                        .filter(|c| !c.is_empty())
+                        .map(|c| c.to_string())
+                        .collect(),
+                })
+            }
+            _ => Err(bad()),
+        }
+    }
+}
+
+/// Where announcements go. A multicast group is joined on `interface`, any
+/// other address, like 255.255.255.255, is treated as a broadcast one.
+#[derive(Debug, Clone)]
+pub struct DiscoveryConfig {
+    pub group: SocketAddrV4,
+    /// Interface to announce and listen on, any when unspecified
+    pub interface: Ipv4Addr,
+    /// Time without announcements after which a sensor is forgotten
+    pub expire_after: Duration,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            group: DEFAULT_GROUP,
+            interface: Ipv4Addr::UNSPECIFIED,
+            expire_after: DEFAULT_ANNOUNCE_INTERVAL * 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredSensor {
+    pub announcement: Announcement,
+    /// Address the announcement came from
+    pub source: SocketAddr,
+    pub last_seen: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscoveryEvent {
+    /// A new sensor, or a known one announcing something different
+    Found(Announcement),
+    /// The sensor with this name stopped announcing itself
+    Lost(String),
+}
+
+/// Live registry of the sensors announcing themselves
+pub struct Discovery {
+    local_addr: SocketAddr,
+    sensors: Arc<Mutex<HashMap<String, DiscoveredSensor>>>,
+    events: broadcast::Sender<DiscoveryEvent>,
+    receiver: Option<JoinHandle<()>>,
+}
+
+impl Discovery {
+    pub async fn start(config: DiscoveryConfig) -> Result<Discovery, DiscoveryError> {
+        let socket = bind(SocketAddrV4::new(
+            Ipv4Addr::UNSPECIFIED,
+            config.group.port(),
+        ))?;
+
+        if config.group.ip().is_multicast() {
+            socket
+                .join_multicast_v4(*config.group.ip(), config.interface)
+                .map_err(|e| DiscoveryError::BindError(e.to_string()))?;
+        }
+        let local_addr = socket
+            .local_addr()
+            .map_err(|e| DiscoveryError::BindError(e.to_string()))?;
+
+        let sensors = Arc::new(Mutex::new(HashMap::new()));
+        let (events, _) = broadcast::channel(64);
+
+        let receiver = tokio::spawn(run_discovery(
+            socket,
+            config.expire_after,
+            sensors.clone(),
+            events.clone(),
+        ));
+
+        Ok(Self {
+            local_addr,
+            sensors,
+            events,
+            receiver: Some(receiver),
+        })
+    }
+
+    /// Address the socket is bound to, with the actual port when the group
+    /// port is 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Sensors heard from recently, sorted by name
+    pub fn sensors(&self) -> Vec<DiscoveredSensor> {
+        let mut sensors: Vec<_> = self.sensors.lock().unwrap().values().cloned().collect();
+        sensors.sort_by(|a, b| a.announcement.name.cmp(&b.announcement.name));
+
+        sensors
+    }
+
+    pub fn get(&self, name: &str) -> Option<DiscoveredSensor> {
+        self.sensors.lock().unwrap().get(name).cloned()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DiscoveryEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stops listening and releases the socket before returning
+    pub async fn shutdown(mut self) {
+        if let Some(receiver) = self.receiver.take() {
+            receiver.abort();
+            let _r = receiver.await;
+        }
+    }
+}
+
+impl Drop for Discovery {
+    fn drop(&mut self) {
+        if let Some(receiver) = &self.receiver {
+            receiver.abort();
+        }
+    }
+}
+
+async fn run_discovery(
+    socket: UdpSocket,
+    expire_after: Duration,
+    sensors: Arc<Mutex<HashMap<String, DiscoveredSensor>>>,
+    events: broadcast::Sender<DiscoveryEvent>,
+) {
+    let mut expiry = tokio::time::interval((expire_after / 4).max(Duration::from_millis(1)));
+
+    loop {
+        let mut buf = [0; MAX_ANNOUNCEMENT_SIZE];
+
+        let received = tokio::select! {
+            received = socket.recv_from(&mut buf) => received,
+            _ = expiry.tick() => {
+                let mut sensors = sensors.lock().unwrap();
+
+                sensors.retain(|name, sensor| {
+                    let alive = sensor.last_seen.elapsed() <= expire_after;
+
+                    if !alive {
+                        let _r = events.send(DiscoveryEvent::Lost(name.clone()));
+                    }
+
+                    alive
+                });
+                continue;
+            }
+        };
+
+        // Errors and foreign datagrams on a shared group are expected, skip them
+        let (announcement, source) = match received {
+            Ok((len, source)) => match Announcement::decode(&buf[..len]) {
+                Ok(announcement) => (announcement, source),
+                Err(_) => continue,
+            },
+            Err(_) => continue,
+        };
+
+        let sensor = DiscoveredSensor {
+            announcement: announcement.clone(),
+            source,
+            last_seen: Instant::now(),
+        };
+
+        let previous = sensors
+            .lock()
+            .unwrap()
+            .insert(announcement.name.clone(), sensor);
+
+        if previous.map(|previous| previous.announcement) != Some(announcement.clone()) {
+            let _r = events.send(DiscoveryEvent::Found(announcement));
+        }
+    }
+}
+
+/// Announces a sensor every `interval` until shut down or dropped
+pub struct Announcer {
+    pub announcement: Announcement,
+
+    sender: Option<JoinHandle<()>>,
+}
+
+impl Announcer {
+    pub async fn start(
+        announcement: Announcement,
+        config: &DiscoveryConfig,
+        interval: Duration,
+    ) -> Result<Announcer, DiscoveryError> {
+        let bytes = announcement.encode()?;
+        let socket = bind(SocketAddrV4::new(config.interface, 0))?;
+        let group = config.group;
+
+        if group.ip().is_multicast() {
+            socket
+                .set_multicast_loop_v4(true)
+                .map_err(|e| DiscoveryError::BindError(e.to_string()))?;
+        } else {
+            socket
+                .set_broadcast(true)
+                .map_err(|e| DiscoveryError::BindError(e.to_string()))?;
+        }
+
+        // The first announcement goes out before returning, so a failing
+        // network is reported to the caller
+        socket
+            .send_to(&bytes, group)
+            .await
+            .map_err(|e| DiscoveryError::SendError(e.to_string()))?;
+
+        let sender = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                let _r = socket.send_to(&bytes, group).await;
+            }
+        });
+
+        Ok(Self {
+            announcement,
+            sender: Some(sender),
+        })
+    }
+
+    /// Stops announcing; the sensor is forgotten once its entry expires
+    pub async fn shutdown(mut self) {
+        if let Some(sender) = self.sender.take() {
+            sender.abort();
+            let _r = sender.await;
+        }
+    }
+}
+
+impl Drop for Announcer {
+    fn drop(&mut self) {
+        if let Some(sender) = &self.sender {
+            sender.abort();
+        }
+    }
+}
+
+// Several listeners on one host share the group port, and announcements
+// leave through `address`'s interface rather than the default route
+fn bind(address: SocketAddrV4) -> Result<UdpSocket, DiscoveryError> {
+    let bind_error = |e: std::io::Error| DiscoveryError::BindError(e.to_string());
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)).map_err(bind_error)?;
+    socket.set_reuse_address(true).map_err(bind_error)?;
+    socket.set_nonblocking(true).map_err(bind_error)?;
+
+    if !address.ip().is_unspecified() {
+        socket
+            .set_multicast_if_v4(address.ip())
+            .map_err(bind_error)?;
+    }
+
+    socket.bind(&address.into()).map_err(bind_error)?;
+
+    UdpSocket::from_std(socket.into()).map_err(bind_error)
+}
@@ -0,0 +1,171 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, watch};
+
+use crate::alert::{AlertEvent, AlertRule};
+use crate::history::{Reading, TempStats, DEFAULT_HISTORY_CAPACITY};
+use crate::metric::Metric;
+use crate::receiver::{Receiver, ReceiverConfig};
+use crate::temperature::{Calibration, TemperatureUnit};
+use crate::thermometer::{Liveness, ReceiverStats, ThermometerError, DEFAULT_STALE_AFTER};
+
+#[derive(Debug, Clone)]
+pub struct EnvironmentConfig {
+    /// Accept only datagrams of this device, any device when `None`
+    pub device_id: Option<u32>,
+    /// Number of temperature readings kept for history queries
+    pub history_capacity: usize,
+    /// Time without readings after which the sensor goes offline
+    pub stale_after: Duration,
+    /// Rules checked against every accepted temperature
+    pub alert_rules: Vec<AlertRule>,
+    /// Correction applied to every temperature as it arrives
+    pub calibration: Calibration,
+    /// Unit of the temperature in the status line
+    pub unit: TemperatureUnit,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            device_id: None,
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            stale_after: DEFAULT_STALE_AFTER,
+            alert_rules: Vec::new(),
+            calibration: Calibration::default(),
+            unit: TemperatureUnit::default(),
+        }
+    }
+}
+
+impl From<EnvironmentConfig> for ReceiverConfig {
+    fn from(config: EnvironmentConfig) -> Self {
+        Self {
+            device_id: config.device_id,
+            accept_legacy: false,
+            jitter: 0.0,
+            history_capacity: config.history_capacity,
+            stale_after: config.stale_after,
+            alert_rules: config.alert_rules,
+            calibration: config.calibration,
+        }
+    }
+}
+
+/// Receives multi-metric readings on `address`. Plain thermometer datagrams
+/// are accepted too, as temperature only readings.
+pub struct EnvironmentSensor {
+    pub name: String,
+    pub address: String,
+
+    unit: TemperatureUnit,
+    receiver: Receiver,
+}
+
+impl EnvironmentSensor {
+    pub async fn new(name: String, address: String) -> Result<EnvironmentSensor, ThermometerError> {
+        Self::with_config(name, address, EnvironmentConfig::default()).await
+    }
+
+    pub async fn with_config(
+        name: String,
+        address: String,
+        config: EnvironmentConfig,
+    ) -> Result<EnvironmentSensor, ThermometerError> {
+        let unit = config.unit;
+        let receiver = Receiver::bind(&name, &address, config.into()).await?;
+
+        Ok(Self {
+            name,
+            address,
+            unit,
+            receiver,
+        })
+    }
+
+    /// Address the socket is bound to, with the actual port when bound to 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.receiver.local_addr()
+    }
+
+    /// Stops receiving and releases the socket before returning
+    pub async fn shutdown(self) {
+        self.receiver.shutdown().await;
+    }
+
+    /// Latest value of the metric, temperatures in Celsius
+    pub fn get(&self, metric: Metric) -> Result<f32, ThermometerError> {
+        self.receiver.check_liveness()?;
+
+        self.receiver
+            .get(metric)
+            .ok_or_else(|| ThermometerError::MissingMetric(metric.to_string()))
+    }
+
+    /// Latest value of every metric the sensor reported
+    pub fn metrics(&self) -> Result<Vec<(Metric, f32)>, ThermometerError> {
+        self.receiver.check_liveness()?;
+
+        Ok(self.receiver.all())
+    }
+
+    pub fn get_status(&self) -> String {
+        let metrics = match self.metrics() {
+            Ok(metrics) => metrics,
+            Err(_) => return self.receiver.offline_status(&self.name),
+        };
+
+        if metrics.is_empty() {
+            return format!("[{}] waiting for a reading", self.name);
+        }
+
+        let values: Vec<_> = metrics
+            .iter()
+            .map(|(metric, value)| format!("{}: {}", metric, metric.format(*value, self.unit)))
+            .collect();
+
+        format!("[{}] {}", self.name, values.join(", "))
+    }
+
+    pub fn since_last_reading(&self) -> Option<Duration> {
+        self.receiver.since_last_reading()
+    }
+
+    pub fn liveness(&self) -> Liveness {
+        self.receiver.liveness()
+    }
+
+    /// Changes on every online/offline transition
+    pub fn watch_liveness(&self) -> watch::Receiver<Liveness> {
+        self.receiver.watch_liveness()
+    }
+
+    /// Alerts raised and cleared by temperatures from now on
+    pub fn subscribe_alerts(&self) -> broadcast::Receiver<AlertEvent> {
+        self.receiver.subscribe_alerts()
+    }
+
+    /// Names of the rules currently in alert
+    pub fn active_alerts(&self) -> Vec<String> {
+        self.receiver.active_alerts()
+    }
+
+    /// Latest socket error the receiver recovered from, cleared by the call
+    pub fn take_error(&self) -> Option<ThermometerError> {
+        self.receiver.take_error()
+    }
+
+    pub fn stats(&self) -> ReceiverStats {
+        self.receiver.stats()
+    }
+
+    /// Temperatures received during the last `window`, oldest first
+    pub fn history(&self, window: Duration) -> Vec<Reading> {
+        self.receiver.history(window)
+    }
+
+    pub fn history_stats(&self, window: Duration) -> Option<TempStats> {
+        self.receiver.history_stats(window)
+    }
+}
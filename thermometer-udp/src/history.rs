@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+/// 24 hours of readings at one reading per second
+pub const DEFAULT_HISTORY_CAPACITY: usize = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    pub temperature: f32,
+    pub timestamp: SystemTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempStats {
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+/// Bounded buffer of readings, the oldest ones are dropped when it is full
+#[derive(Debug, Clone)]
+pub struct History {
+    readings: VecDeque<Reading>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            readings: VecDeque::with_capacity(capacity.min(DEFAULT_HISTORY_CAPACITY)),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, reading: Reading) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.readings.len() == self.capacity {
+            self.readings.pop_front();
+        }
+
+        self.readings.push_back(reading);
+    }
+
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<Reading> {
+        self.readings.back().copied()
+    }
+
+    /// Readings taken at `since` or later, oldest first
+    pub fn since(&self, since: SystemTime) -> impl Iterator<Item = &Reading> {
+        self.readings
+            .iter()
+            .filter(move |reading| reading.timestamp >= since)
+    }
+
+    /// Readings of the last `window` before `now`
+    pub fn window(&self, window: Duration, now: SystemTime) -> Vec<Reading> {
+        self.since(window_start(window, now)).copied().collect()
+    }
+
+    pub fn stats(&self, since: SystemTime) -> Option<TempStats> {
+        let mut readings = self.since(since).map(|reading| reading.temperature);
+        let first = readings.next()?;
+
+        let (count, min, max, sum) = readings.fold(
+            (1, first, first, first as f64),
+            |(count, min, max, sum), temperature| {
+                (
+                    count + 1,
+                    min.min(temperature),
+                    max.max(temperature),
+                    sum + temperature as f64,
+                )
+            },
+        );
+
+        Some(TempStats {
+            count,
+            min,
+            max,
+            mean: (sum / count as f64) as f32,
+        })
+    }
+
+    /// Nearest-rank percentile, `percentile` goes from 0 to 100
+    pub fn percentile(&self, since: SystemTime, percentile: f32) -> Option<f32> {
+        let mut temperatures: Vec<f32> = self
+            .since(since)
+            .map(|reading| reading.temperature)
+            .collect();
+
+        if temperatures.is_empty() || !(0.0..=100.0).contains(&percentile) {
+            return None;
+        }
+
+        temperatures.sort_by(f32::total_cmp);
+
+        let rank = (percentile / 100.0 * temperatures.len() as f32).ceil() as usize;
+
+        Some(temperatures[rank.saturating_sub(1)])
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+pub fn window_start(window: Duration, now: SystemTime) -> SystemTime {
+    now.checked_sub(window).unwrap_or(SystemTime::UNIX_EPOCH)
+}
@@ -0,0 +1,413 @@
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+use crate::datagram::{self, SequenceCheck, SequenceTracker, MAX_SENSOR_DATAGRAM_SIZE};
+use crate::history::{History, Reading};
+use crate::metric::Metric;
+use crate::temperature::{Calibration, Temperature, TemperatureUnit};
+use crate::thermometer::{
+    Liveness, ReceiverStats, ThermometerError, DEFAULT_STALE_AFTER, RECEIVE_ERROR_BACKOFF,
+};
+
+/// An hour of readings at one reading per second, for every sensor
+pub const DEFAULT_HUB_HISTORY_CAPACITY: usize = 60 * 60;
+
+/// Which unknown senders get registered on their first reading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoRegister {
+    Any,
+    Only(HashSet<u32>),
+    Never,
+}
+
+#[derive(Debug, Clone)]
+pub struct HubConfig {
+    pub auto_register: AutoRegister,
+    /// Number of readings kept for every sensor
+    pub history_capacity: usize,
+    /// Time without readings after which a sensor goes offline
+    pub stale_after: Duration,
+    /// Unit of the temperatures in status lines
+    pub unit: TemperatureUnit,
+}
+
+impl Default for HubConfig {
+    fn default() -> Self {
+        Self {
+            auto_register: AutoRegister::Any,
+            history_capacity: DEFAULT_HUB_HISTORY_CAPACITY,
+            stale_after: DEFAULT_STALE_AFTER,
+            unit: TemperatureUnit::default(),
+        }
+    }
+}
+
+/// Datagrams the hub couldn't attribute to any sensor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HubStats {
+    pub malformed: u64,
+    /// Readings of unregistered devices that weren't auto-registered
+    pub rejected: u64,
+}
+
+/// Snapshot of a sensor known to the hub
+#[derive(Debug, Clone, PartialEq)]
+pub struct HubSensor {
+    pub device_id: u32,
+    pub name: String,
+    pub temperature: Option<Temperature>,
+    pub calibration: Calibration,
+    pub liveness: Liveness,
+    pub since_last_reading: Option<Duration>,
+    /// Address the latest reading came from
+    pub source: Option<SocketAddr>,
+    pub stats: ReceiverStats,
+}
+
+struct SensorEntry {
+    name: String,
+    registered: Instant,
+    temperature: Option<Temperature>,
+    calibration: Calibration,
+    last_reading: Option<Instant>,
+    source: Option<SocketAddr>,
+    history: History,
+    stats: ReceiverStats,
+}
+
+#[derive(Default)]
+struct HubState {
+    sensors: HashMap<u32, SensorEntry>,
+    names: HashMap<String, u32>,
+    sequences: SequenceTracker,
+    stats: HubStats,
+    last_error: Option<ThermometerError>,
+}
+
+/// Receives readings of many thermometers on one address, telling them apart
+/// by the device id of their datagrams. Only the temperature of sensor
+/// datagrams is kept.
+pub struct ThermometerHub {
+    pub address: String,
+
+    config: HubConfig,
+    local_addr: SocketAddr,
+    state: Arc<Mutex<HubState>>,
+    receiver: Option<JoinHandle<()>>,
+}
+
+impl ThermometerHub {
+    pub async fn new(address: String) -> Result<ThermometerHub, ThermometerError> {
+        Self::with_config(address, HubConfig::default()).await
+    }
+
+    pub async fn with_config(
+        address: String,
+        config: HubConfig,
+    ) -> Result<ThermometerHub, ThermometerError> {
+        let socket = UdpSocket::bind(&address)
+            .await
+            .map_err(|e| ThermometerError::BindError(e.to_string()))?;
+        let local_addr = socket
+            .local_addr()
+            .map_err(|e| ThermometerError::BindError(e.to_string()))?;
+
+        let state = Arc::new(Mutex::new(HubState::default()));
+        let receiver = tokio::spawn(run_hub(socket, config.clone(), state.clone()));
+
+        Ok(Self {
+            address,
+            config,
+            local_addr,
+            state,
+            receiver: Some(receiver),
+        })
+    }
+
+    /// Address the socket is bound to, with the actual port when bound to 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Names a device, or renames it if it's already known. Registered
+    /// devices are accepted regardless of `auto_register`.
+    pub fn register(&self, device_id: u32, name: &str) -> Result<(), ThermometerError> {
+        let mut state = self.state.lock().unwrap();
+
+        match state.names.get(name) {
+            Some(id) if *id == device_id => return Ok(()),
+            Some(_) => return Err(ThermometerError::NameTaken(name.to_string())),
+            None => (),
+        }
+
+        match state.sensors.get_mut(&device_id) {
+            Some(sensor) => {
+                let previous = std::mem::replace(&mut sensor.name, name.to_string());
+                state.names.remove(&previous);
+            }
+            None => {
+                let sensor = SensorEntry::new(name, self.config.history_capacity);
+                state.sensors.insert(device_id, sensor);
+            }
+        }
+
+        state.names.insert(name.to_string(), device_id);
+
+        Ok(())
+    }
+
+    /// Forgets the sensor, its readings are auto-registered again if allowed
+    pub fn unregister(&self, name: &str) -> Result<(), ThermometerError> {
+        let mut state = self.state.lock().unwrap();
+        let device_id = state
+            .names
+            .remove(name)
+            .ok_or_else(|| ThermometerError::UnknownSensor(name.to_string()))?;
+
+        state.sensors.remove(&device_id);
+        state.sequences.forget(device_id);
+
+        Ok(())
+    }
+
+    pub fn sensor(&self, name: &str) -> Option<HubSensor> {
+        let state = self.state.lock().unwrap();
+        let device_id = *state.names.get(name)?;
+
+        Some(state.sensors[&device_id].snapshot(device_id, self.config.stale_after))
+    }
+
+    pub fn sensor_by_id(&self, device_id: u32) -> Option<HubSensor> {
+        let state = self.state.lock().unwrap();
+
+        state
+            .sensors
+            .get(&device_id)
+            .map(|sensor| sensor.snapshot(device_id, self.config.stale_after))
+    }
+
+    /// All known sensors, sorted by name
+    pub fn sensors(&self) -> Vec<HubSensor> {
+        let state = self.state.lock().unwrap();
+        let mut sensors: Vec<_> = state
+            .sensors
+            .iter()
+            .map(|(id, sensor)| sensor.snapshot(*id, self.config.stale_after))
+            .collect();
+        sensors.sort_by(|a, b| a.name.cmp(&b.name));
+
+        sensors
+    }
+
+    /// Corrects the sensor's readings from now on
+    pub fn calibrate(&self, name: &str, calibration: Calibration) -> Result<(), ThermometerError> {
+        let mut state = self.state.lock().unwrap();
+        let device_id = *state
+            .names
+            .get(name)
+            .ok_or_else(|| ThermometerError::UnknownSensor(name.to_string()))?;
+
+        state
+            .sensors
+            .get_mut(&device_id)
+            .expect("Names only refer to registered sensors")
+            .calibration = calibration;
+
+        Ok(())
+    }
+
+    /// Latest temperature of the sensor in Celsius, unless it went silent
+    pub fn get_temperature(&self, name: &str) -> Result<f32, ThermometerError> {
+        self.temperature(name)
+            .map(|temperature| temperature.celsius())
+    }
+
+    pub fn temperature(&self, name: &str) -> Result<Temperature, ThermometerError> {
+        let sensor = self
+            .sensor(name)
+            .ok_or_else(|| ThermometerError::UnknownSensor(name.to_string()))?;
+
+        match (sensor.liveness, sensor.temperature) {
+            (Liveness::Online, Some(temperature)) => Ok(temperature),
+            (Liveness::Online, None) => Err(ThermometerError::NoReading),
+            (Liveness::Offline, _) => Err(ThermometerError::Stale {
+                since_last_reading: sensor.since_last_reading,
+            }),
+        }
+    }
+
+    pub fn get_status(&self, name: &str) -> Result<String, ThermometerError> {
+        let sensor = self
+            .sensor(name)
+            .ok_or_else(|| ThermometerError::UnknownSensor(name.to_string()))?;
+
+        let status = match (sensor.liveness, sensor.since_last_reading) {
+            (Liveness::Online, _) => match sensor.temperature {
+                Some(temperature) => format!(
+                    "[{}] current temperature: {}",
+                    name,
+                    temperature.display_in(self.config.unit)
+                ),
+                None => format!("[{}] waiting for a reading", name),
+            },
+            (Liveness::Offline, Some(age)) => {
+                format!("[{}] stale: no reading for {}s", name, age.as_secs())
+            }
+            (Liveness::Offline, None) => format!("[{}] offline: no reading received", name),
+        };
+
+        Ok(status)
+    }
+
+    /// Readings of the sensor during the last `window`, oldest first
+    pub fn history(&self, name: &str, window: Duration) -> Result<Vec<Reading>, ThermometerError> {
+        let state = self.state.lock().unwrap();
+        let device_id = state
+            .names
+            .get(name)
+            .ok_or_else(|| ThermometerError::UnknownSensor(name.to_string()))?;
+
+        Ok(state.sensors[device_id]
+            .history
+            .window(window, SystemTime::now()))
+    }
+
+    pub fn stats(&self) -> HubStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// Latest socket error the hub recovered from, cleared by the call
+    pub fn take_error(&self) -> Option<ThermometerError> {
+        self.state.lock().unwrap().last_error.take()
+    }
+
+    /// Stops receiving and releases the socket before returning
+    pub async fn shutdown(mut self) {
+        if let Some(receiver) = self.receiver.take() {
+            receiver.abort();
+            let _r = receiver.await;
+        }
+    }
+}
+
+impl Drop for ThermometerHub {
+    fn drop(&mut self) {
+        if let Some(receiver) = &self.receiver {
+            receiver.abort();
+        }
+    }
+}
+
+impl SensorEntry {
+    fn new(name: &str, history_capacity: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            registered: Instant::now(),
+            temperature: None,
+            calibration: Calibration::default(),
+            last_reading: None,
+            source: None,
+            history: History::new(history_capacity),
+            stats: ReceiverStats::default(),
+        }
+    }
+
+    fn snapshot(&self, device_id: u32, stale_after: Duration) -> HubSensor {
+        let last_heard = self.last_reading.unwrap_or(self.registered);
+
+        HubSensor {
+            device_id,
+            name: self.name.clone(),
+            temperature: self.temperature,
+            calibration: self.calibration,
+            liveness: Liveness::of(last_heard, stale_after),
+            since_last_reading: self.last_reading.map(|last_reading| last_reading.elapsed()),
+            source: self.source,
+            stats: self.stats,
+        }
+    }
+}
+
+// Name given to auto-registered sensors until they are renamed
+fn default_name(device_id: u32) -> String {
+    format!("thermometer-{}", device_id)
+}
+
+async fn run_hub(socket: UdpSocket, config: HubConfig, state: Arc<Mutex<HubState>>) {
+    loop {
+        // One byte more than the largest datagram, so longer payloads are told apart
+        let mut buf = [0; MAX_SENSOR_DATAGRAM_SIZE + 1];
+
+        // Errors caused by one sender must not stop the others
+        let (len, source) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(e) => {
+                state.lock().unwrap().last_error =
+                    Some(ThermometerError::ReceiveError(e.to_string()));
+                tokio::time::sleep(RECEIVE_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let mut state = state.lock().unwrap();
+
+        let datagram = match datagram::decode_reading(&buf[..len]) {
+            Ok(datagram) => datagram,
+            Err(_) => {
+                state.stats.malformed += 1;
+                continue;
+            }
+        };
+
+        if !state.sensors.contains_key(&datagram.device_id) {
+            let allowed = match &config.auto_register {
+                AutoRegister::Any => true,
+                AutoRegister::Only(allowed) => allowed.contains(&datagram.device_id),
+                AutoRegister::Never => false,
+            };
+            let name = default_name(datagram.device_id);
+
+            if !allowed || state.names.contains_key(&name) {
+                state.stats.rejected += 1;
+                continue;
+            }
+
+            let sensor = SensorEntry::new(&name, config.history_capacity);
+            state.sensors.insert(datagram.device_id, sensor);
+            state.names.insert(name, datagram.device_id);
+        }
+
+        let check = state.sequences.check_sensor(&datagram);
+        let sensor = state
+            .sensors
+            .get_mut(&datagram.device_id)
+            .expect("Sensor is registered above");
+
+        match check {
+            SequenceCheck::New => {
+                sensor.stats.accepted += 1;
+                sensor.last_reading = Some(Instant::now());
+                sensor.source = Some(source);
+
+                if let Some(temperature) = datagram.value(Metric::Temperature) {
+                    let temperature = sensor
+                        .calibration
+                        .apply(Temperature::from_celsius(temperature));
+
+                    sensor.temperature = Some(temperature);
+                    sensor.history.push(Reading {
+                        temperature: temperature.celsius(),
+                        timestamp: datagram.timestamp,
+                    });
+                }
+            }
+            SequenceCheck::Duplicate => sensor.stats.duplicates += 1,
+            SequenceCheck::OutOfOrder => sensor.stats.out_of_order += 1,
+        }
+    }
+}
@@ -1,2 +1,19 @@
+pub mod alert;
+pub mod datagram;
+#[cfg(feature = "net")]
+pub mod discovery;
+#[cfg(feature = "net")]
+pub mod environment;
+pub mod history;
+#[cfg(feature = "net")]
+pub mod hub;
+pub mod metric;
+#[cfg(feature = "net")]
+mod receiver;
+#[cfg(feature = "net")]
+pub mod sender;
 pub mod temp;
+pub mod temperature;
+#[cfg(feature = "net")]
 pub mod thermometer;
+pub mod waveform;
@@ -0,0 +1,88 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::temperature::{Temperature, TemperatureUnit};
+
+/// Quantity measured by an environmental sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    /// Degrees Celsius
+    Temperature,
+    /// Relative humidity, percent
+    Humidity,
+    /// Parts per million
+    Co2,
+    /// Hectopascals
+    Pressure,
+}
+
+pub const METRICS: [Metric; 4] = [
+    Metric::Temperature,
+    Metric::Humidity,
+    Metric::Co2,
+    Metric::Pressure,
+];
+
+impl Metric {
+    /// Identifies the metric in datagrams
+    pub fn id(&self) -> u8 {
+        match self {
+            Metric::Temperature => 1,
+            Metric::Humidity => 2,
+            Metric::Co2 => 3,
+            Metric::Pressure => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        METRICS.into_iter().find(|metric| metric.id() == id)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Temperature => "temperature",
+            Metric::Humidity => "humidity",
+            Metric::Co2 => "CO2",
+            Metric::Pressure => "pressure",
+        }
+    }
+
+    pub fn unit_symbol(&self) -> &'static str {
+        match self {
+            Metric::Temperature => TemperatureUnit::Celsius.symbol(),
+            Metric::Humidity => "%",
+            Metric::Co2 => "ppm",
+            Metric::Pressure => "hPa",
+        }
+    }
+
+    /// The value with its unit, temperatures converted to `unit`
+    pub fn format(&self, value: f32, unit: TemperatureUnit) -> String {
+        match self {
+            Metric::Temperature => Temperature::from_celsius(value)
+                .display_in(unit)
+                .to_string(),
+            metric => format!("{}{}", value, metric.unit_symbol()),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        METRICS
+            .into_iter()
+            .find(|metric| metric.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown metric {:?}", s))
+    }
+}
@@ -0,0 +1,334 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use tokio::net::UdpSocket;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+
+use crate::alert::{AlertEvaluator, AlertEvent, AlertRule};
+use crate::datagram::{self, SequenceCheck, SequenceTracker, MAX_SENSOR_DATAGRAM_SIZE};
+use crate::history::{self, History, Reading, TempStats};
+use crate::metric::Metric;
+use crate::temp::Temp;
+use crate::temperature::{Calibration, Temperature};
+use crate::thermometer::{Liveness, ReceiverStats, ThermometerError, RECEIVE_ERROR_BACKOFF};
+
+/// Settings of the receive loop, shared by thermometers and environmental
+/// sensors
+#[derive(Debug, Clone)]
+pub(crate) struct ReceiverConfig {
+    pub device_id: Option<u32>,
+    pub accept_legacy: bool,
+    pub jitter: f32,
+    pub history_capacity: usize,
+    pub stale_after: Duration,
+    pub alert_rules: Vec<AlertRule>,
+    pub calibration: Calibration,
+}
+
+/// Receives readings on a socket until shut down or dropped. History and
+/// alerts follow the temperature.
+pub(crate) struct Receiver {
+    local_addr: SocketAddr,
+    shared: Arc<Shared>,
+    stale_after: Duration,
+    liveness: watch::Receiver<Liveness>,
+    alerts: broadcast::Sender<AlertEvent>,
+    task: Option<JoinHandle<()>>,
+}
+
+// State updated by the receive loop and read through the handle
+struct Shared {
+    values: Temp,
+    history: Mutex<History>,
+    stats: Mutex<ReceiverStats>,
+    started: Instant,
+    last_reading: Mutex<Option<Instant>>,
+    last_error: Mutex<Option<ThermometerError>>,
+    alerts: Mutex<AlertEvaluator>,
+}
+
+impl Receiver {
+    pub async fn bind(
+        name: &str,
+        address: &str,
+        config: ReceiverConfig,
+    ) -> Result<Receiver, ThermometerError> {
+        let socket = UdpSocket::bind(address)
+            .await
+            .map_err(|e| ThermometerError::BindError(e.to_string()))?;
+        let local_addr = socket
+            .local_addr()
+            .map_err(|e| ThermometerError::BindError(e.to_string()))?;
+
+        let shared = Arc::new(Shared {
+            values: Temp::with_jitter(config.jitter),
+            history: Mutex::new(History::new(config.history_capacity)),
+            stats: Mutex::new(ReceiverStats::default()),
+            started: Instant::now(),
+            last_reading: Mutex::new(None),
+            last_error: Mutex::new(None),
+            alerts: Mutex::new(AlertEvaluator::new(name, config.alert_rules.clone())),
+        });
+        let stale_after = config.stale_after;
+        let (liveness_sender, liveness) = watch::channel(Liveness::Online);
+        let (alerts, _) = broadcast::channel(64);
+
+        let task = tokio::spawn(run_receiver(
+            socket,
+            config,
+            shared.clone(),
+            liveness_sender,
+            alerts.clone(),
+        ));
+
+        Ok(Self {
+            local_addr,
+            shared,
+            stale_after,
+            liveness,
+            alerts,
+            task: Some(task),
+        })
+    }
+
+    /// Stops receiving and releases the socket before returning
+    pub async fn shutdown(mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+            let _r = task.await;
+        }
+    }
+
+    /// Address the socket is bound to, with the actual port when bound to 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Latest value of the metric, whether or not the sender is still heard
+    pub fn get(&self, metric: Metric) -> Option<f32> {
+        self.shared.values.get(metric)
+    }
+
+    pub fn all(&self) -> Vec<(Metric, f32)> {
+        self.shared.values.all()
+    }
+
+    /// Fails with `Stale` once the sender went silent
+    pub fn check_liveness(&self) -> Result<(), ThermometerError> {
+        match self.liveness() {
+            Liveness::Online => Ok(()),
+            Liveness::Offline => Err(ThermometerError::Stale {
+                since_last_reading: self.since_last_reading(),
+            }),
+        }
+    }
+
+    /// Status line of a sender that went silent
+    pub fn offline_status(&self, name: &str) -> String {
+        match self.since_last_reading() {
+            Some(age) => format!("[{}] stale: no reading for {}s", name, age.as_secs()),
+            None => format!("[{}] offline: no reading received", name),
+        }
+    }
+
+    pub fn since_last_reading(&self) -> Option<Duration> {
+        self.shared
+            .last_reading
+            .lock()
+            .unwrap()
+            .map(|last_reading| last_reading.elapsed())
+    }
+
+    pub fn liveness(&self) -> Liveness {
+        let last_heard = self
+            .shared
+            .last_reading
+            .lock()
+            .unwrap()
+            .unwrap_or(self.shared.started);
+
+        Liveness::of(last_heard, self.stale_after)
+    }
+
+    pub fn watch_liveness(&self) -> watch::Receiver<Liveness> {
+        self.liveness.clone()
+    }
+
+    pub fn subscribe_alerts(&self) -> broadcast::Receiver<AlertEvent> {
+        self.alerts.subscribe()
+    }
+
+    pub fn active_alerts(&self) -> Vec<String> {
+        self.shared.alerts.lock().unwrap().active()
+    }
+
+    pub fn take_error(&self) -> Option<ThermometerError> {
+        self.shared.last_error.lock().unwrap().take()
+    }
+
+    pub fn stats(&self) -> ReceiverStats {
+        *self.shared.stats.lock().unwrap()
+    }
+
+    pub fn history(&self, window: Duration) -> Vec<Reading> {
+        self.shared
+            .history
+            .lock()
+            .unwrap()
+            .window(window, SystemTime::now())
+    }
+
+    pub fn history_stats(&self, window: Duration) -> Option<TempStats> {
+        let since = history::window_start(window, SystemTime::now());
+
+        self.shared.history.lock().unwrap().stats(since)
+    }
+
+    pub fn history_percentile(&self, window: Duration, percentile: f32) -> Option<f32> {
+        let since = history::window_start(window, SystemTime::now());
+
+        self.shared
+            .history
+            .lock()
+            .unwrap()
+            .percentile(since, percentile)
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+async fn run_receiver(
+    socket: UdpSocket,
+    config: ReceiverConfig,
+    shared: Arc<Shared>,
+    liveness: watch::Sender<Liveness>,
+    alerts: broadcast::Sender<AlertEvent>,
+) {
+    let mut sequences = SequenceTracker::default();
+    let mut last_heard = shared.started;
+
+    loop {
+        // One byte more than the largest datagram, so longer payloads are told apart
+        let mut buf = [0; MAX_SENSOR_DATAGRAM_SIZE + 1];
+        let is_online = *liveness.borrow() == Liveness::Online;
+        // No deadline when `stale_after` reaches past what an `Instant` holds
+        let stale_at = last_heard.checked_add(config.stale_after);
+
+        let received = tokio::select! {
+            received = socket.recv_from(&mut buf) => received,
+            _ = sleep_until(stale_at), if is_online => {
+                liveness.send_replace(Liveness::Offline);
+                continue;
+            }
+        };
+
+        let len = match received {
+            Ok((len, _)) => len,
+            Err(e) => {
+                *shared.last_error.lock().unwrap() =
+                    Some(ThermometerError::ReceiveError(e.to_string()));
+                tokio::time::sleep(RECEIVE_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let received = receive(
+            &buf[..len],
+            &config,
+            &mut sequences,
+            &mut shared.stats.lock().unwrap(),
+        );
+
+        let (timestamp, metrics) = match received {
+            Some(received) => received,
+            None => continue,
+        };
+
+        for (metric, value) in metrics {
+            let value = match metric {
+                Metric::Temperature => config
+                    .calibration
+                    .apply(Temperature::from_celsius(value))
+                    .celsius(),
+                _ => value,
+            };
+
+            shared.values.set(metric, value);
+
+            if metric == Metric::Temperature {
+                let reading = Reading {
+                    temperature: value,
+                    timestamp,
+                };
+
+                shared.history.lock().unwrap().push(reading);
+
+                for event in shared.alerts.lock().unwrap().observe(reading) {
+                    let _r = alerts.send(event);
+                }
+            }
+        }
+
+        last_heard = Instant::now();
+        *shared.last_reading.lock().unwrap() = Some(last_heard);
+
+        if !is_online {
+            liveness.send_replace(Liveness::Online);
+        }
+    }
+}
+
+// Moment of measurement and the metrics carried by the payload, if it should
+// be taken
+// Never completes without a deadline
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn receive(
+    payload: &[u8],
+    config: &ReceiverConfig,
+    sequences: &mut SequenceTracker,
+    stats: &mut ReceiverStats,
+) -> Option<(SystemTime, Vec<(Metric, f32)>)> {
+    let received = match datagram::decode_reading(payload) {
+        Ok(datagram) if config.device_id.unwrap_or(datagram.device_id) != datagram.device_id => {
+            return None
+        }
+        Ok(datagram) => match sequences.check_sensor(&datagram) {
+            SequenceCheck::New => (datagram.timestamp, datagram.metrics),
+            SequenceCheck::Duplicate => {
+                stats.duplicates += 1;
+                return None;
+            }
+            SequenceCheck::OutOfOrder => {
+                stats.out_of_order += 1;
+                return None;
+            }
+        },
+        Err(_) => match datagram::decode_legacy(payload) {
+            Ok(temperature) if config.accept_legacy => {
+                (SystemTime::now(), vec![(Metric::Temperature, temperature)])
+            }
+            _ => {
+                stats.malformed += 1;
+                return None;
+            }
+        },
+    };
+
+    stats.accepted += 1;
+
+    Some(received)
+}
@@ -0,0 +1,120 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::net::UdpSocket;
+
+use crate::datagram::{Datagram, SensorDatagram};
+use crate::metric::Metric;
+use crate::waveform::Waveform;
+
+/// Emits temperature readings to a thermometer
+pub struct Sender {
+    pub target: String,
+    pub device_id: u32,
+    /// Send bare 4-byte temperatures understood by legacy receivers
+    pub legacy: bool,
+
+    socket: UdpSocket,
+    sequence: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum SenderError {
+    #[error("Failed to bind sender socket: {:?}", .0)]
+    BindError(String),
+    #[error("Failed to send reading: {:?}", .0)]
+    SendError(String),
+    #[error("Failed to find local address: {:?}", .0)]
+    AddressError(String),
+}
+
+impl Sender {
+    pub async fn new(target: &str, device_id: u32) -> Result<Sender, SenderError> {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .await
+            .map_err(|e| SenderError::BindError(e.to_string()))?;
+
+        Ok(Self {
+            target: target.to_string(),
+            device_id,
+            legacy: false,
+            socket,
+            sequence: 0,
+        })
+    }
+
+    /// Address the readings leave from, on the interface that routes to
+    /// `target`
+    pub async fn local_addr(&self) -> Result<SocketAddr, SenderError> {
+        let address_error = |e: std::io::Error| SenderError::AddressError(e.to_string());
+        let port = self.socket.local_addr().map_err(address_error)?.port();
+
+        // Connecting a UDP socket only picks a route, nothing is sent
+        let probe = UdpSocket::bind("0.0.0.0:0").await.map_err(address_error)?;
+        probe.connect(&self.target).await.map_err(address_error)?;
+        let ip = probe.local_addr().map_err(address_error)?.ip();
+
+        Ok(SocketAddr::new(ip, port))
+    }
+
+    pub async fn send(&mut self, temperature: f32) -> Result<Datagram, SenderError> {
+        let datagram = Datagram::new(self.device_id, self.sequence, temperature);
+
+        let result = match self.legacy {
+            true => {
+                self.socket
+                    .send_to(&temperature.to_be_bytes(), &self.target)
+                    .await
+            }
+            false => self.socket.send_to(&datagram.encode(), &self.target).await,
+        };
+
+        result.map_err(|e| SenderError::SendError(e.to_string()))?;
+        self.sequence += 1;
+
+        Ok(datagram)
+    }
+
+    /// Sends several metrics in one datagram, legacy receivers can't read it
+    pub async fn send_metrics(
+        &mut self,
+        metrics: &[(Metric, f32)],
+    ) -> Result<SensorDatagram, SenderError> {
+        let datagram = SensorDatagram::new(self.device_id, self.sequence, metrics);
+
+        self.socket
+            .send_to(&datagram.encode(), &self.target)
+            .await
+            .map_err(|e| SenderError::SendError(e.to_string()))?;
+        self.sequence += 1;
+
+        Ok(datagram)
+    }
+
+    /// Sends a sample of `waveform` every `interval` until it is exhausted or
+    /// `count` readings were sent. The waveform sees the time as a multiple of
+    /// `interval`, so the values don't depend on timer precision.
+    pub async fn run(
+        &mut self,
+        waveform: &mut dyn Waveform,
+        interval: Duration,
+        count: Option<u64>,
+    ) -> Result<u64, SenderError> {
+        let mut ticker = tokio::time::interval(interval);
+        let mut sent = 0;
+
+        while !matches!(count, Some(count) if sent >= count) {
+            let temperature = match waveform.sample(interval.mul_f64(sent as f64)) {
+                Some(temperature) => temperature,
+                None => break,
+            };
+
+            ticker.tick().await;
+            self.send(temperature).await?;
+            sent += 1;
+        }
+
+        Ok(sent)
+    }
+}
@@ -1,24 +1,76 @@
+use std::collections::BTreeMap;
 use std::sync::Mutex;
 
 use rand::Rng;
 
+use crate::metric::Metric;
+
+/// Noise added to every temperature read by default, emulates an imprecise
+/// thermometer
+pub const DEFAULT_JITTER: f32 = 2.0;
+
+/// Latest value of every metric a sensor reported
 #[derive(Debug)]
-pub struct Temp(Mutex<f32>);
+pub struct Temp {
+    values: Mutex<BTreeMap<Metric, f32>>,
+    jitter: f32,
+}
 
 impl Temp {
-    pub fn get_temp(&self) -> f32 {
-        let current = *self.0.lock().unwrap();
+    /// `jitter` of zero reports values exactly as they were set
+    pub fn new(initial: f32, jitter: f32) -> Self {
+        let temp = Self::with_jitter(jitter);
+        temp.set_temp(initial);
+        temp
+    }
 
-        current + rand::thread_rng().gen_range(-2.0..2.0)
+    /// Has no values until the first reading arrives
+    pub fn with_jitter(jitter: f32) -> Self {
+        Self {
+            values: Mutex::new(BTreeMap::new()),
+            jitter,
+        }
+    }
+
+    pub fn get_temp(&self) -> Option<f32> {
+        self.get(Metric::Temperature)
     }
 
     pub fn set_temp(&self, val: f32) {
-        *self.0.lock().unwrap() = val;
+        self.set(Metric::Temperature, val);
+    }
+
+    pub fn get(&self, metric: Metric) -> Option<f32> {
+        let value = *self.values.lock().unwrap().get(&metric)?;
+
+        Some(self.add_jitter(metric, value))
+    }
+
+    pub fn set(&self, metric: Metric, value: f32) {
+        self.values.lock().unwrap().insert(metric, value);
+    }
+
+    /// Every metric reported so far, in `METRICS` order
+    pub fn all(&self) -> Vec<(Metric, f32)> {
+        self.values
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(metric, value)| (*metric, self.add_jitter(*metric, *value)))
+            .collect()
+    }
+
+    // Only temperatures are noisy, other metrics are reported as they were set
+    fn add_jitter(&self, metric: Metric, value: f32) -> f32 {
+        match metric == Metric::Temperature && self.jitter > 0.0 {
+            true => value + rand::thread_rng().gen_range(-self.jitter..self.jitter),
+            false => value,
+        }
     }
 }
 
 impl Default for Temp {
     fn default() -> Self {
-        Self(Mutex::new(rand::thread_rng().gen_range(-2.0..28.0)))
+        Self::with_jitter(DEFAULT_JITTER)
     }
 }
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+const ABSOLUTE_ZERO_CELSIUS: f32 = -273.15;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl TemperatureUnit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+            TemperatureUnit::Kelvin => "K",
+        }
+    }
+}
+
+impl FromStr for TemperatureUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "c" | "celsius" => Ok(TemperatureUnit::Celsius),
+            "f" | "fahrenheit" => Ok(TemperatureUnit::Fahrenheit),
+            "k" | "kelvin" => Ok(TemperatureUnit::Kelvin),
+            _ => Err(format!("Unknown temperature unit {:?}", s)),
+        }
+    }
+}
+
+/// Temperature independent of a unit, stored in degrees Celsius like the
+/// readings on the wire. Serialized as the number of degrees Celsius.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Temperature {
+    celsius: f32,
+}
+
+impl Temperature {
+    pub fn new(value: f32, unit: TemperatureUnit) -> Self {
+        let celsius = match unit {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            TemperatureUnit::Kelvin => value + ABSOLUTE_ZERO_CELSIUS,
+        };
+
+        Self { celsius }
+    }
+
+    pub fn from_celsius(celsius: f32) -> Self {
+        Self { celsius }
+    }
+
+    pub fn from_fahrenheit(fahrenheit: f32) -> Self {
+        Self::new(fahrenheit, TemperatureUnit::Fahrenheit)
+    }
+
+    pub fn from_kelvin(kelvin: f32) -> Self {
+        Self::new(kelvin, TemperatureUnit::Kelvin)
+    }
+
+    pub fn celsius(&self) -> f32 {
+        self.celsius
+    }
+
+    pub fn fahrenheit(&self) -> f32 {
+        self.value_in(TemperatureUnit::Fahrenheit)
+    }
+
+    pub fn kelvin(&self) -> f32 {
+        self.value_in(TemperatureUnit::Kelvin)
+    }
+
+    pub fn value_in(&self, unit: TemperatureUnit) -> f32 {
+        match unit {
+            TemperatureUnit::Celsius => self.celsius,
+            TemperatureUnit::Fahrenheit => self.celsius * 9.0 / 5.0 + 32.0,
+            TemperatureUnit::Kelvin => self.celsius - ABSOLUTE_ZERO_CELSIUS,
+        }
+    }
+
+    /// Formats the value with the unit symbol, like "78.8°F"
+    pub fn display_in(&self, unit: TemperatureUnit) -> TemperatureDisplay {
+        TemperatureDisplay {
+            temperature: *self,
+            unit,
+        }
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display_in(TemperatureUnit::Celsius).fmt(f)
+    }
+}
+
+pub struct TemperatureDisplay {
+    temperature: Temperature,
+    unit: TemperatureUnit,
+}
+
+impl fmt::Display for TemperatureDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.temperature.value_in(self.unit);
+
+        match f.precision() {
+            Some(precision) => write!(f, "{:.*}{}", precision, value, self.unit.symbol()),
+            None => write!(f, "{}{}", value, self.unit.symbol()),
+        }
+    }
+}
+
+/// Correction of a sensor's raw readings: `raw * gain + offset`, in Celsius
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Calibration {
+    pub offset: f32,
+    pub gain: f32,
+}
+
+impl Calibration {
+    pub fn new(offset: f32, gain: f32) -> Self {
+        Self { offset, gain }
+    }
+
+    pub fn apply(&self, raw: Temperature) -> Temperature {
+        Temperature::from_celsius(raw.celsius() * self.gain + self.offset)
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self::new(0.0, 1.0)
+    }
+}
@@ -1,61 +1,229 @@
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::net::UdpSocket;
+use tokio::sync::{broadcast, watch};
 
-use crate::temp::Temp;
+use crate::alert::{AlertEvent, AlertRule};
+use crate::history::{Reading, TempStats, DEFAULT_HISTORY_CAPACITY};
+use crate::metric::Metric;
+use crate::receiver::{Receiver, ReceiverConfig};
+use crate::temp::DEFAULT_JITTER;
+use crate::temperature::{Calibration, Temperature, TemperatureUnit};
 
+/// Receives readings on `address` until shut down or dropped. Sensor
+/// datagrams are accepted too, for their temperature.
 pub struct Thermometer {
     pub name: String,
     pub address: String,
 
-    temperature: Arc<Temp>,
+    unit: TemperatureUnit,
+    receiver: Receiver,
+}
+
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(10);
+
+// Pause after a socket error, so a persistent one doesn't spin the loop
+pub(crate) const RECEIVE_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Whether the sender is still heard from. A new thermometer is online for
+/// the staleness timeout before its first reading has to arrive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    Online,
+    Offline,
+}
+
+impl Liveness {
+    /// Online while `last_heard` is at most `stale_after` ago
+    pub fn of(last_heard: Instant, stale_after: Duration) -> Self {
+        match last_heard.elapsed() > stale_after {
+            true => Liveness::Offline,
+            false => Liveness::Online,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ThermometerConfig {
+    /// Accept only datagrams of this device, any device when `None`
+    pub device_id: Option<u32>,
+    /// Also accept bare 4-byte temperatures from legacy senders
+    pub accept_legacy: bool,
+    /// Synthetic noise added to reported temperatures, zero disables it
+    pub jitter: f32,
+    /// Number of readings kept for history queries
+    pub history_capacity: usize,
+    /// Time without readings after which the thermometer goes offline
+    pub stale_after: Duration,
+    /// Rules checked against every accepted reading
+    pub alert_rules: Vec<AlertRule>,
+    /// Correction applied to every reading as it arrives
+    pub calibration: Calibration,
+    /// Unit of the temperature in the status line
+    pub unit: TemperatureUnit,
+}
+
+impl Default for ThermometerConfig {
+    fn default() -> Self {
+        Self {
+            device_id: None,
+            accept_legacy: false,
+            jitter: DEFAULT_JITTER,
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            stale_after: DEFAULT_STALE_AFTER,
+            alert_rules: Vec::new(),
+            calibration: Calibration::default(),
+            unit: TemperatureUnit::default(),
+        }
+    }
+}
+
+impl From<ThermometerConfig> for ReceiverConfig {
+    fn from(config: ThermometerConfig) -> Self {
+        Self {
+            device_id: config.device_id,
+            accept_legacy: config.accept_legacy,
+            jitter: config.jitter,
+            history_capacity: config.history_capacity,
+            stale_after: config.stale_after,
+            alert_rules: config.alert_rules,
+            calibration: config.calibration,
+        }
+    }
+}
+
+/// Datagrams handled by a thermometer since it started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReceiverStats {
+    pub accepted: u64,
+    pub duplicates: u64,
+    pub out_of_order: u64,
+    /// Payloads that are neither a valid datagram nor an accepted legacy one
+    pub malformed: u64,
 }
 
 #[derive(Debug, Error)]
 pub enum ThermometerError {
     #[error("Failed to connect to server: {:?}", .0)]
     BindError(String),
+    #[error("No fresh readings, the last one arrived {:?} ago", .since_last_reading)]
+    Stale {
+        since_last_reading: Option<Duration>,
+    },
+    #[error("No reading received yet")]
+    NoReading,
+    #[error("Failed to receive datagram: {:?}", .0)]
+    ReceiveError(String),
+    #[error("No sensor with name: {:?}", .0)]
+    UnknownSensor(String),
+    #[error("Sensor name is already taken: {:?}", .0)]
+    NameTaken(String),
+    #[error("Sensor hasn't reported metric: {:?}", .0)]
+    MissingMetric(String),
 }
 
 impl Thermometer {
     pub async fn new(name: String, address: String) -> Result<Thermometer, ThermometerError> {
-        let temperature = Arc::new(Temp::default());
-        let socket = UdpSocket::bind(&address).await;
-
-        let temp_cloned = temperature.clone();
-
-        match socket {
-            Ok(socket) => {
-                tokio::spawn(async move {
-                    loop {
-                        let mut buf: [u8; 4] = [0; 4];
-
-                        socket
-                            .recv_from(&mut buf)
-                            .await
-                            .expect("Unable to receive data");
-
-                        let temp = f32::from_be_bytes(buf);
+        Self::with_config(name, address, ThermometerConfig::default()).await
+    }
 
-                        temp_cloned.set_temp(temp);
-                    }
-                });
-            }
-            Err(e) => return Err(ThermometerError::BindError(e.to_string())),
-        }
+    pub async fn with_config(
+        name: String,
+        address: String,
+        config: ThermometerConfig,
+    ) -> Result<Thermometer, ThermometerError> {
+        let unit = config.unit;
+        let receiver = Receiver::bind(&name, &address, config.into()).await?;
 
         Ok(Self {
             name,
             address,
-            temperature,
+            unit,
+            receiver,
         })
     }
 
+    /// Address the socket is bound to, with the actual port when bound to 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.receiver.local_addr()
+    }
+
+    /// Stops receiving and releases the socket before returning
+    pub async fn shutdown(self) {
+        self.receiver.shutdown().await;
+    }
+
     pub fn get_status(&self) -> String {
-        format!(
-            "[{}] current temperature: {}",
-            self.name,
-            &self.temperature.get_temp()
-        )
+        if self.liveness() == Liveness::Offline {
+            return self.receiver.offline_status(&self.name);
+        }
+
+        match self.receiver.get(Metric::Temperature) {
+            Some(temperature) => format!(
+                "[{}] current temperature: {}",
+                self.name,
+                Temperature::from_celsius(temperature).display_in(self.unit)
+            ),
+            None => format!("[{}] waiting for a reading", self.name),
+        }
+    }
+
+    /// Same as `get_temperature`, with the unit attached
+    pub fn temperature(&self) -> Result<Temperature, ThermometerError> {
+        self.get_temperature().map(Temperature::from_celsius)
+    }
+
+    /// Latest temperature in Celsius, unless the sender went silent
+    pub fn get_temperature(&self) -> Result<f32, ThermometerError> {
+        self.receiver.check_liveness()?;
+
+        self.receiver
+            .get(Metric::Temperature)
+            .ok_or(ThermometerError::NoReading)
+    }
+
+    pub fn since_last_reading(&self) -> Option<Duration> {
+        self.receiver.since_last_reading()
+    }
+
+    pub fn liveness(&self) -> Liveness {
+        self.receiver.liveness()
+    }
+
+    /// Changes on every online/offline transition
+    pub fn watch_liveness(&self) -> watch::Receiver<Liveness> {
+        self.receiver.watch_liveness()
+    }
+
+    /// Alerts raised and cleared by readings from now on
+    pub fn subscribe_alerts(&self) -> broadcast::Receiver<AlertEvent> {
+        self.receiver.subscribe_alerts()
+    }
+
+    /// Names of the rules currently in alert
+    pub fn active_alerts(&self) -> Vec<String> {
+        self.receiver.active_alerts()
+    }
+
+    /// Latest socket error the receiver recovered from, cleared by the call
+    pub fn take_error(&self) -> Option<ThermometerError> {
+        self.receiver.take_error()
+    }
+
+    pub fn stats(&self) -> ReceiverStats {
+        self.receiver.stats()
+    }
+
+    /// Readings received during the last `window`, oldest first
+    pub fn history(&self, window: Duration) -> Vec<Reading> {
+        self.receiver.history(window)
+    }
+
+    pub fn history_stats(&self, window: Duration) -> Option<TempStats> {
+        self.receiver.history_stats(window)
+    }
+
+    pub fn history_percentile(&self, window: Duration, percentile: f32) -> Option<f32> {
+        self.receiver.history_percentile(window, percentile)
     }
 }
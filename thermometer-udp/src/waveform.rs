@@ -0,0 +1,153 @@
+use std::f32::consts::PI;
+use std::io::BufRead;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+pub const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Source of emulated temperatures. `elapsed` is the time since the first
+/// sample, `None` means the source is exhausted.
+pub trait Waveform: Send {
+    fn sample(&mut self, elapsed: Duration) -> Option<f32>;
+}
+
+pub struct Constant(pub f32);
+
+impl Waveform for Constant {
+    fn sample(&mut self, _elapsed: Duration) -> Option<f32> {
+        Some(self.0)
+    }
+}
+
+/// Day/night cycle: `mean` at the start, peaks at `mean + amplitude` a
+/// quarter `period` later.
+pub struct Sine {
+    pub mean: f32,
+    pub amplitude: f32,
+    pub period: Duration,
+}
+
+impl Waveform for Sine {
+    fn sample(&mut self, elapsed: Duration) -> Option<f32> {
+        let phase = elapsed.as_secs_f32() / self.period.as_secs_f32() * 2.0 * PI;
+
+        Some(self.mean + self.amplitude * phase.sin())
+    }
+}
+
+/// Moves by at most `step` degrees per sample, seeded so runs repeat. A step
+/// that isn't a positive number keeps the temperature where it started.
+pub struct RandomWalk {
+    current: f32,
+    step: f32,
+    rng: StdRng,
+}
+
+impl RandomWalk {
+    pub fn new(start: f32, step: f32, seed: u64) -> Self {
+        Self {
+            current: start,
+            step,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Waveform for RandomWalk {
+    fn sample(&mut self, _elapsed: Duration) -> Option<f32> {
+        let value = self.current;
+
+        if self.step > 0.0 && self.step.is_finite() {
+            self.current += self.rng.gen_range(-self.step..=self.step);
+        }
+
+        Some(value)
+    }
+}
+
+/// Recorded temperatures, one per sample
+pub struct Replay {
+    readings: std::vec::IntoIter<f32>,
+}
+
+impl Replay {
+    pub fn new(readings: Vec<f32>) -> Self {
+        Self {
+            readings: readings.into_iter(),
+        }
+    }
+
+    /// The temperature is the last column of each line, so both bare values
+    /// and `timestamp,temperature` rows work. Lines that don't end with a
+    /// number, like headers, are skipped.
+    pub fn from_csv<R: BufRead>(reader: R) -> std::io::Result<Self> {
+        let mut readings = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let last_column = line.rsplit(',').next().unwrap_or_default().trim();
+
+            if let Ok(temperature) = last_column.parse::<f32>() {
+                if temperature.is_finite() {
+                    readings.push(temperature);
+                }
+            }
+        }
+
+        Ok(Self::new(readings))
+    }
+}
+
+impl Waveform for Replay {
+    fn sample(&mut self, _elapsed: Duration) -> Option<f32> {
+        self.readings.next()
+    }
+}
+
+/// Parses a waveform spec as accepted by the sender CLI:
+/// `constant:<t>`, `sine:<mean>:<amplitude>[:<period secs>]`,
+/// `walk:<start>:<step>[:<seed>]` or `csv:<path>`.
+pub fn parse_waveform(spec: &str) -> Result<Box<dyn Waveform>, String> {
+    let (kind, args) = spec.split_once(':').unwrap_or((spec, ""));
+
+    if kind == "csv" {
+        let file = std::fs::File::open(args).map_err(|e| e.to_string())?;
+        let replay = Replay::from_csv(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+
+        return Ok(Box::new(replay));
+    }
+
+    let args: Vec<&str> = args.split(':').collect();
+    let number = |arg: &str| -> Result<f32, String> {
+        arg.parse::<f32>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| format!("Bad number {:?}", arg))
+    };
+
+    match (kind, &args[..]) {
+        ("constant", [value]) => Ok(Box::new(Constant(number(value)?))),
+        ("sine", [mean, amplitude, period @ ..]) if period.len() <= 1 => Ok(Box::new(Sine {
+            mean: number(mean)?,
+            amplitude: number(amplitude)?,
+            period: match period {
+                [period] => Duration::try_from_secs_f32(number(period)?)
+                    .ok()
+                    .filter(|period| !period.is_zero())
+                    .ok_or_else(|| format!("Bad period {:?}", period))?,
+                _ => DAY,
+            },
+        })),
+        ("walk", [start, step, seed @ ..]) if seed.len() <= 1 => Ok(Box::new(RandomWalk::new(
+            number(start)?,
+            number(step)?,
+            match seed {
+                [seed] => seed.parse().map_err(|_| format!("Bad seed {:?}", seed))?,
+                _ => 0,
+            },
+        ))),
+        _ => Err(format!("Unknown waveform {:?}", spec)),
+    }
+}
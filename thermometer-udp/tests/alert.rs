@@ -0,0 +1,144 @@
+#![cfg(feature = "net")]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use thermometer_udp::alert::{AlertEvaluator, AlertEvent, AlertRule, Condition};
+use thermometer_udp::history::Reading;
+use thermometer_udp::sender::Sender;
+use thermometer_udp::thermometer::{Thermometer, ThermometerConfig};
+use tokio::time::timeout;
+
+fn reading(secs: u64, temperature: f32) -> Reading {
+    Reading {
+        temperature,
+        timestamp: UNIX_EPOCH + Duration::from_secs(secs),
+    }
+}
+
+// Raised/cleared flags of the events every reading caused
+fn observe_all(evaluator: &mut AlertEvaluator, readings: &[(u64, f32)]) -> Vec<Vec<bool>> {
+    readings
+        .iter()
+        .map(|(secs, temperature)| {
+            evaluator
+                .observe(reading(*secs, *temperature))
+                .iter()
+                .map(|event| matches!(event, AlertEvent::Raised(_)))
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_threshold_with_hysteresis() {
+    let mut evaluator = AlertEvaluator::new(
+        "Hall",
+        vec![AlertRule::below("frost", 5.0).with_hysteresis(1.0)],
+    );
+
+    let events = observe_all(
+        &mut evaluator,
+        &[(0, 6.0), (1, 4.9), (2, 5.5), (3, 4.0), (4, 6.1), (5, 4.5)],
+    );
+
+    assert_eq!(
+        events,
+        [vec![], vec![true], vec![], vec![], vec![false], vec![true]]
+    );
+    assert_eq!(evaluator.active(), ["frost"]);
+}
+
+#[test]
+fn test_min_duration() {
+    let mut evaluator = AlertEvaluator::new(
+        "Hall",
+        vec![AlertRule::above("heat", 30.0).with_min_duration(Duration::from_secs(60))],
+    );
+
+    let events = observe_all(
+        &mut evaluator,
+        &[
+            (0, 31.0),
+            (30, 32.0),
+            (40, 29.0),
+            (50, 31.0),
+            (100, 31.0),
+            (110, 31.0),
+        ],
+    );
+
+    assert_eq!(events, [vec![], vec![], vec![], vec![], vec![], vec![true]]);
+
+    let mut evaluator = AlertEvaluator::new("Hall", vec![AlertRule::above("heat", 30.0)]);
+    let events = evaluator.observe(reading(0, 35.0));
+
+    assert_eq!(events[0].alert().condition, Condition::Above(30.0),);
+    assert_eq!(events[0].alert().value, 35.0);
+}
+
+#[test]
+fn test_rate_of_change() {
+    let mut evaluator = AlertEvaluator::new(
+        "Hall",
+        vec![AlertRule::rate_of_change("window open", 2.0).with_hysteresis(0.5)],
+    );
+
+    // Degrees per minute between readings: -1, -3, -1.8, -1
+    let events = observe_all(
+        &mut evaluator,
+        &[(0, 20.0), (60, 19.0), (120, 16.0), (180, 14.2), (240, 13.2)],
+    );
+
+    assert_eq!(events, [vec![], vec![], vec![true], vec![], vec![false]]);
+
+    match &evaluator.observe(reading(270, 15.2))[..] {
+        [AlertEvent::Raised(alert)] => assert_eq!(alert.value, 4.0),
+        events => panic!("Unexpected events {:?}", events),
+    }
+}
+
+#[tokio::test]
+async fn test_thermometer_emits_alerts() {
+    let thermo = Thermometer::with_config(
+        "Thermo#10".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            jitter: 0.0,
+            alert_rules: vec![AlertRule::below("frost", 3.0).with_hysteresis(1.0)],
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut alerts = thermo.subscribe_alerts();
+    let mut sender = Sender::new(&thermo.local_addr().to_string(), 1)
+        .await
+        .unwrap();
+
+    for temperature in [5.0, 2.5, 3.5, 4.5] {
+        sender.send(temperature).await.unwrap();
+    }
+
+    let raised = timeout(Duration::from_secs(1), alerts.recv())
+        .await
+        .unwrap()
+        .unwrap();
+
+    match raised {
+        AlertEvent::Raised(alert) => {
+            assert_eq!(alert.thermometer, "Thermo#10");
+            assert_eq!(alert.rule, "frost");
+            assert_eq!(alert.value, 2.5);
+        }
+        event => panic!("Unexpected event {:?}", event),
+    }
+
+    let cleared = timeout(Duration::from_secs(1), alerts.recv())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(matches!(cleared, AlertEvent::Cleared(alert) if alert.value == 4.5));
+    assert!(thermo.active_alerts().is_empty());
+}
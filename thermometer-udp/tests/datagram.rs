@@ -0,0 +1,160 @@
+#![cfg(feature = "net")]
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thermometer_udp::datagram::{
+    self, Datagram, DatagramError, SequenceCheck, SequenceTracker, DATAGRAM_SIZE,
+};
+use thermometer_udp::thermometer::{ReceiverStats, Thermometer, ThermometerConfig};
+use tokio::net::UdpSocket;
+
+fn reading(sequence: u64, timestamp_secs: u64) -> Datagram {
+    Datagram {
+        device_id: 7,
+        sequence,
+        timestamp: UNIX_EPOCH + Duration::from_secs(timestamp_secs),
+        temperature: 21.5,
+    }
+}
+
+#[test]
+fn test_roundtrip() {
+    let sent = reading(42, 1_600_000_000);
+
+    assert_eq!(Datagram::decode(&sent.encode()), Ok(sent));
+}
+
+#[test]
+fn test_rejects_garbage() {
+    let mut bytes = reading(1, 1_600_000_000).encode();
+
+    assert_eq!(
+        Datagram::decode(&bytes[..10]),
+        Err(DatagramError::WrongSize(10))
+    );
+
+    bytes[24] ^= 0xff;
+    assert_eq!(
+        Datagram::decode(&bytes),
+        Err(DatagramError::ChecksumMismatch)
+    );
+
+    bytes[0] = b'X';
+    assert_eq!(Datagram::decode(&bytes), Err(DatagramError::BadMagic));
+
+    assert_eq!(datagram::decode_legacy(&20.0f32.to_be_bytes()), Ok(20.0));
+    assert_eq!(
+        datagram::decode_legacy(&bytes),
+        Err(DatagramError::WrongSize(DATAGRAM_SIZE))
+    );
+}
+
+#[test]
+fn test_sequence_tracker() {
+    let mut tracker = SequenceTracker::default();
+
+    assert_eq!(tracker.check(&reading(5, 100)), SequenceCheck::New);
+    assert_eq!(tracker.check(&reading(5, 100)), SequenceCheck::Duplicate);
+    assert_eq!(tracker.check(&reading(4, 99)), SequenceCheck::OutOfOrder);
+    assert_eq!(tracker.check(&reading(7, 102)), SequenceCheck::New);
+
+    // Device restarted and counts from zero again
+    assert_eq!(tracker.check(&reading(0, 200)), SequenceCheck::New);
+    assert_eq!(tracker.check(&reading(1, 201)), SequenceCheck::New);
+}
+
+async fn wait_for_stats(thermo: &Thermometer, expected: ReceiverStats) {
+    for _ in 0..100 {
+        if thermo.stats() == expected {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(thermo.stats(), expected);
+}
+
+#[tokio::test]
+async fn test_thermometer_drops_duplicates_and_garbage() {
+    let thermo = Thermometer::with_config(
+        "Thermo#2".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            device_id: Some(7),
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let now = SystemTime::now();
+
+    let datagrams = [
+        Datagram::new(7, 2, 20.0),
+        Datagram::new(7, 2, 20.0),
+        Datagram {
+            timestamp: now - Duration::from_secs(1),
+            ..Datagram::new(7, 1, 20.0)
+        },
+        Datagram::new(8, 3, 20.0),
+    ];
+
+    for datagram in datagrams {
+        sender
+            .send_to(&datagram.encode(), thermo.local_addr())
+            .await
+            .unwrap();
+    }
+
+    sender
+        .send_to(&20.0f32.to_be_bytes(), thermo.local_addr())
+        .await
+        .unwrap();
+    sender
+        .send_to(&Datagram::new(7, 3, 20.0).encode(), thermo.local_addr())
+        .await
+        .unwrap();
+
+    wait_for_stats(
+        &thermo,
+        ReceiverStats {
+            accepted: 2,
+            duplicates: 1,
+            out_of_order: 1,
+            malformed: 1,
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_legacy_mode() {
+    let thermo = Thermometer::with_config(
+        "Thermo#3".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            accept_legacy: true,
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    sender
+        .send_to(&20.0f32.to_be_bytes(), thermo.local_addr())
+        .await
+        .unwrap();
+
+    wait_for_stats(
+        &thermo,
+        ReceiverStats {
+            accepted: 1,
+            ..ReceiverStats::default()
+        },
+    )
+    .await;
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "net")]
+
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::Duration;
+
+use thermometer_udp::discovery::{
+    Announcement, Announcer, Discovery, DiscoveryConfig, DiscoveryError, DiscoveryEvent,
+};
+use tokio::sync::broadcast;
+use tokio::time::timeout;
+
+// Listens on a free port of the group and returns the config announcers
+// should use to reach it
+async fn start_on_loopback(group: [u8; 4]) -> (Discovery, DiscoveryConfig) {
+    let config = DiscoveryConfig {
+        group: SocketAddrV4::new(group.into(), 0),
+        interface: Ipv4Addr::LOCALHOST,
+        expire_after: Duration::from_millis(300),
+    };
+    let discovery = Discovery::start(config.clone()).await.unwrap();
+    let port = discovery.local_addr().port();
+
+    let config = DiscoveryConfig {
+        group: SocketAddrV4::new(group.into(), port),
+        ..config
+    };
+
+    (discovery, config)
+}
+
+async fn next_event(events: &mut broadcast::Receiver<DiscoveryEvent>) -> DiscoveryEvent {
+    timeout(Duration::from_secs(2), events.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn test_announcement_roundtrip() {
+    let announcement = Announcement::new("Kitchen", 1, "192.168.1.20:50312", &["temperature"]);
+    let bytes = announcement.encode().unwrap();
+
+    assert_eq!(Announcement::decode(&bytes).unwrap(), announcement);
+    assert!(matches!(
+        Announcement::decode(&bytes[..3]),
+        Err(DiscoveryError::BadAnnouncement(_))
+    ));
+    assert!(matches!(
+        Announcement::decode(&20.0f32.to_be_bytes()),
+        Err(DiscoveryError::BadAnnouncement(_))
+    ));
+    assert!(matches!(
+        Announcement::new("Bad\nname", 1, "192.168.1.20:50312", &[]).encode(),
+        Err(DiscoveryError::BadAnnouncement(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_discovers_sensors_on_multicast_group() {
+    let (discovery, config) = start_on_loopback([239, 255, 33, 59]).await;
+    let mut events = discovery.subscribe();
+
+    let kitchen = Announcement::new("Kitchen", 1, "127.0.0.1:3360", &["temperature"]);
+    let announcer = Announcer::start(kitchen.clone(), &config, Duration::from_millis(50))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        next_event(&mut events).await,
+        DiscoveryEvent::Found(kitchen.clone())
+    );
+    assert_eq!(discovery.get("Kitchen").unwrap().announcement, kitchen);
+
+    let bedroom = Announcement::new("Bedroom", 2, "127.0.0.1:3361", &["temperature", "humidity"]);
+    let _bedroom_announcer = Announcer::start(bedroom.clone(), &config, Duration::from_millis(50))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        next_event(&mut events).await,
+        DiscoveryEvent::Found(bedroom.clone())
+    );
+
+    let names: Vec<_> = discovery
+        .sensors()
+        .into_iter()
+        .map(|sensor| sensor.announcement.name)
+        .collect();
+
+    assert_eq!(names, ["Bedroom", "Kitchen"]);
+
+    announcer.shutdown().await;
+
+    assert_eq!(
+        next_event(&mut events).await,
+        DiscoveryEvent::Lost("Kitchen".to_string())
+    );
+    assert!(discovery.get("Kitchen").is_none());
+    assert!(discovery.get("Bedroom").is_some());
+}
+
+#[tokio::test]
+async fn test_discovers_sensors_by_broadcast() {
+    let (discovery, config) = start_on_loopback([127, 255, 255, 255]).await;
+    let mut events = discovery.subscribe();
+
+    let hall = Announcement::new("Hall", 3, "127.0.0.1:3363", &["temperature"]);
+    let _announcer = Announcer::start(hall.clone(), &config, Duration::from_millis(50))
+        .await
+        .unwrap();
+
+    assert_eq!(next_event(&mut events).await, DiscoveryEvent::Found(hall));
+}
@@ -0,0 +1,255 @@
+#![cfg(feature = "net")]
+
+use std::time::{Duration, SystemTime};
+
+use thermometer_udp::datagram::{
+    Datagram, DatagramError, SensorDatagram, MAX_SENSOR_DATAGRAM_SIZE,
+};
+use thermometer_udp::environment::{EnvironmentConfig, EnvironmentSensor};
+use thermometer_udp::metric::Metric;
+use thermometer_udp::sender::Sender;
+use thermometer_udp::temp::Temp;
+use thermometer_udp::temperature::TemperatureUnit;
+use thermometer_udp::thermometer::{Liveness, Thermometer, ThermometerConfig, ThermometerError};
+
+#[test]
+fn test_sensor_datagram_roundtrip() {
+    let sent = SensorDatagram::new(
+        3,
+        42,
+        &[
+            (Metric::Temperature, 21.5),
+            (Metric::Humidity, 40.0),
+            (Metric::Co2, 600.0),
+            (Metric::Humidity, 45.0),
+        ],
+    );
+    let decoded = SensorDatagram::decode(&sent.encode()).unwrap();
+
+    assert_eq!(decoded.metrics.len(), 3);
+    assert_eq!(decoded.value(Metric::Humidity), Some(45.0));
+    assert_eq!(decoded.value(Metric::Pressure), None);
+    assert_eq!(decoded.sequence, 42);
+}
+
+#[test]
+fn test_sensor_datagram_rejects_garbage() {
+    let mut bytes = SensorDatagram::new(3, 1, &[(Metric::Pressure, 1013.0)]).encode();
+
+    assert_eq!(
+        SensorDatagram::decode(&bytes[..bytes.len() - 1]),
+        Err(DatagramError::WrongSize(bytes.len() - 1))
+    );
+    assert_eq!(
+        SensorDatagram::decode(&Datagram::new(3, 1, 20.0).encode()),
+        Err(DatagramError::UnsupportedVersion(1))
+    );
+
+    bytes[24] = 99;
+    assert_eq!(
+        SensorDatagram::decode(&bytes),
+        Err(DatagramError::ChecksumMismatch)
+    );
+}
+
+#[test]
+fn test_sensor_datagram_size_limits() {
+    let all = SensorDatagram::new(
+        3,
+        1,
+        &[
+            (Metric::Temperature, 21.5),
+            (Metric::Humidity, 40.0),
+            (Metric::Co2, 600.0),
+            (Metric::Pressure, 1013.0),
+        ],
+    )
+    .encode();
+
+    assert_eq!(all.len(), MAX_SENSOR_DATAGRAM_SIZE);
+    assert!(SensorDatagram::decode(&all).is_ok());
+
+    // `new` merges repeated metrics, so the datagram is built by hand
+    let repeated = SensorDatagram {
+        device_id: 3,
+        sequence: 1,
+        timestamp: SystemTime::now(),
+        metrics: vec![(Metric::Humidity, 40.0), (Metric::Humidity, 45.0)],
+    };
+
+    assert_eq!(
+        SensorDatagram::decode(&repeated.encode()),
+        Err(DatagramError::DuplicateMetric(Metric::Humidity.id()))
+    );
+}
+
+#[test]
+fn test_metric_store() {
+    let store = Temp::with_jitter(0.0);
+
+    assert_eq!(store.get(Metric::Humidity), None);
+    assert_eq!(store.get_temp(), None);
+
+    store.set(Metric::Pressure, 1013.0);
+    store.set(Metric::Humidity, 40.0);
+
+    assert_eq!(
+        store.all(),
+        [(Metric::Humidity, 40.0), (Metric::Pressure, 1013.0)]
+    );
+    assert_eq!("co2".parse(), Ok(Metric::Co2));
+    assert_eq!(
+        Metric::Temperature.format(25.0, TemperatureUnit::Fahrenheit),
+        "77°F"
+    );
+}
+
+#[test]
+fn test_jitter_only_applies_to_temperature() {
+    let store = Temp::with_jitter(2.0);
+
+    store.set_temp(20.0);
+    store.set(Metric::Humidity, 40.0);
+    store.set(Metric::Co2, 800.0);
+
+    for _ in 0..100 {
+        let temperature = store.get_temp().unwrap();
+
+        assert!((18.0..22.0).contains(&temperature));
+        assert_eq!(store.get(Metric::Humidity), Some(40.0));
+        assert_eq!(
+            store.all()[1..],
+            [(Metric::Humidity, 40.0), (Metric::Co2, 800.0)]
+        );
+    }
+}
+
+async fn wait_for_accepted(sensor: &EnvironmentSensor, accepted: u64) {
+    for _ in 0..100 {
+        if sensor.stats().accepted == accepted {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(sensor.stats().accepted, accepted);
+}
+
+#[tokio::test]
+async fn test_environment_sensor() -> Result<(), ThermometerError> {
+    let sensor = EnvironmentSensor::with_config(
+        "Living room".to_string(),
+        "127.0.0.1:0".to_string(),
+        EnvironmentConfig {
+            device_id: Some(3),
+            ..EnvironmentConfig::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(sensor.get_status(), "[Living room] waiting for a reading");
+
+    let mut sender = Sender::new(&sensor.local_addr().to_string(), 3)
+        .await
+        .unwrap();
+    sender
+        .send_metrics(&[(Metric::Temperature, 21.5), (Metric::Humidity, 40.0)])
+        .await
+        .unwrap();
+
+    wait_for_accepted(&sensor, 1).await;
+
+    assert_eq!(sensor.get(Metric::Humidity)?, 40.0);
+    assert!(matches!(
+        sensor.get(Metric::Co2),
+        Err(ThermometerError::MissingMetric(_))
+    ));
+    assert_eq!(
+        sensor.get_status(),
+        "[Living room] temperature: 21.5°C, humidity: 40%"
+    );
+
+    // Plain thermometer datagrams update the temperature only
+    sender.send(23.0).await.unwrap();
+    wait_for_accepted(&sensor, 2).await;
+
+    assert_eq!(
+        sensor.metrics()?,
+        [(Metric::Temperature, 23.0), (Metric::Humidity, 40.0)]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_environment_sensor_history_and_liveness() -> Result<(), ThermometerError> {
+    let sensor = EnvironmentSensor::with_config(
+        "Attic".to_string(),
+        "127.0.0.1:0".to_string(),
+        EnvironmentConfig {
+            stale_after: Duration::from_millis(200),
+            ..EnvironmentConfig::default()
+        },
+    )
+    .await?;
+
+    let mut liveness = sensor.watch_liveness();
+    let mut sender = Sender::new(&sensor.local_addr().to_string(), 4)
+        .await
+        .unwrap();
+    sender
+        .send_metrics(&[(Metric::Humidity, 60.0), (Metric::Temperature, 12.0)])
+        .await
+        .unwrap();
+    sender.send_metrics(&[(Metric::Co2, 800.0)]).await.unwrap();
+
+    wait_for_accepted(&sensor, 2).await;
+
+    let history = sensor.history(Duration::from_secs(60));
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].temperature, 12.0);
+    assert!(sensor.take_error().is_none());
+
+    tokio::time::timeout(Duration::from_secs(1), liveness.changed())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(*liveness.borrow(), Liveness::Offline);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_thermometer_takes_temperature_of_sensor_datagrams() {
+    let thermo = Thermometer::with_config(
+        "Thermo#12".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            jitter: 0.0,
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut sender = Sender::new(&thermo.local_addr().to_string(), 5)
+        .await
+        .unwrap();
+    sender
+        .send_metrics(&[(Metric::Humidity, 55.0), (Metric::Temperature, 19.5)])
+        .await
+        .unwrap();
+
+    for _ in 0..100 {
+        if thermo.stats().accepted == 1 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(thermo.get_temperature().unwrap(), 19.5);
+    assert_eq!(thermo.stats().malformed, 0);
+}
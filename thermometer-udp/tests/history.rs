@@ -0,0 +1,122 @@
+#![cfg(feature = "net")]
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thermometer_udp::history::{History, Reading, TempStats};
+use thermometer_udp::sender::Sender;
+use thermometer_udp::thermometer::{Thermometer, ThermometerConfig};
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn history_of(temperatures: &[f32]) -> History {
+    let mut history = History::new(100);
+
+    for (i, temperature) in temperatures.iter().enumerate() {
+        history.push(Reading {
+            temperature: *temperature,
+            timestamp: at(i as u64 * 60),
+        });
+    }
+
+    history
+}
+
+#[test]
+fn test_capacity_drops_oldest() {
+    let mut history = History::new(3);
+
+    for i in 0..5 {
+        history.push(Reading {
+            temperature: i as f32,
+            timestamp: at(i),
+        });
+    }
+
+    assert_eq!(history.len(), 3);
+    assert_eq!(
+        history
+            .since(at(0))
+            .map(|r| r.temperature)
+            .collect::<Vec<_>>(),
+        vec![2.0, 3.0, 4.0]
+    );
+    assert_eq!(history.latest().unwrap().temperature, 4.0);
+}
+
+#[test]
+fn test_window_stats() {
+    let history = history_of(&[10.0, 30.0, 18.0, 22.0, 20.0]);
+
+    assert_eq!(
+        history.stats(at(0)),
+        Some(TempStats {
+            count: 5,
+            min: 10.0,
+            max: 30.0,
+            mean: 20.0,
+        })
+    );
+    assert_eq!(
+        history.stats(at(120)),
+        Some(TempStats {
+            count: 3,
+            min: 18.0,
+            max: 22.0,
+            mean: 20.0,
+        })
+    );
+    assert_eq!(history.stats(at(1000)), None);
+    assert_eq!(history.window(Duration::from_secs(60), at(240)).len(), 2);
+}
+
+#[test]
+fn test_percentile() {
+    let history = history_of(&[15.0, 20.0, 35.0, 40.0, 50.0]);
+
+    assert_eq!(history.percentile(at(0), 0.0), Some(15.0));
+    assert_eq!(history.percentile(at(0), 30.0), Some(20.0));
+    assert_eq!(history.percentile(at(0), 50.0), Some(35.0));
+    assert_eq!(history.percentile(at(0), 100.0), Some(50.0));
+    assert_eq!(history.percentile(at(0), 101.0), None);
+}
+
+#[tokio::test]
+async fn test_thermometer_without_jitter() {
+    let thermo = Thermometer::with_config(
+        "Thermo#5".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            jitter: 0.0,
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut sender = Sender::new(&thermo.local_addr().to_string(), 1)
+        .await
+        .unwrap();
+
+    for temperature in [19.0, 21.0, 23.0] {
+        sender.send(temperature).await.unwrap();
+    }
+
+    for _ in 0..100 {
+        if thermo.history(Duration::from_secs(60)).len() == 3 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let stats = thermo.history_stats(Duration::from_secs(60)).unwrap();
+
+    assert_eq!((stats.count, stats.min, stats.max), (3, 19.0, 23.0));
+    assert_eq!(thermo.get_status(), "[Thermo#5] current temperature: 23°C");
+    assert_eq!(
+        thermo.history_percentile(Duration::from_secs(60), 50.0),
+        Some(21.0)
+    );
+}
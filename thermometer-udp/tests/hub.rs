@@ -0,0 +1,164 @@
+#![cfg(feature = "net")]
+
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+use thermometer_udp::datagram::Datagram;
+use thermometer_udp::hub::{AutoRegister, HubConfig, HubStats, ThermometerHub};
+use thermometer_udp::metric::Metric;
+use thermometer_udp::sender::Sender;
+use thermometer_udp::thermometer::ThermometerError;
+use tokio::net::UdpSocket;
+
+async fn wait_until(condition: impl Fn() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert!(condition());
+}
+
+#[tokio::test]
+async fn test_demultiplexes_by_device_id() -> Result<(), ThermometerError> {
+    let hub = ThermometerHub::new("127.0.0.1:0".to_string()).await?;
+    hub.register(1, "Kitchen")?;
+
+    assert!(matches!(
+        hub.get_temperature("Kitchen"),
+        Err(ThermometerError::NoReading)
+    ));
+
+    let mut kitchen = Sender::new(&hub.local_addr().to_string(), 1).await.unwrap();
+    let mut bedroom = Sender::new(&hub.local_addr().to_string(), 2).await.unwrap();
+
+    kitchen.send(21.5).await.unwrap();
+    bedroom.send(18.0).await.unwrap();
+
+    wait_until(|| hub.sensors().len() == 2 && hub.get_temperature("Kitchen").is_ok()).await;
+
+    assert_eq!(hub.get_temperature("Kitchen")?, 21.5);
+    assert_eq!(hub.get_temperature("thermometer-2")?, 18.0);
+    assert_eq!(
+        hub.get_status("Kitchen")?,
+        "[Kitchen] current temperature: 21.5°C"
+    );
+
+    hub.register(2, "Bedroom")?;
+
+    assert_eq!(hub.sensor("Bedroom").unwrap().device_id, 2);
+    assert!(hub.sensor("thermometer-2").is_none());
+    assert!(matches!(
+        hub.register(2, "Kitchen"),
+        Err(ThermometerError::NameTaken(_))
+    ));
+    assert!(matches!(
+        hub.get_temperature("Hall"),
+        Err(ThermometerError::UnknownSensor(_))
+    ));
+
+    let names: Vec<_> = hub
+        .sensors()
+        .into_iter()
+        .map(|sensor| sensor.name)
+        .collect();
+    assert_eq!(names, ["Bedroom", "Kitchen"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_allow_list() -> Result<(), ThermometerError> {
+    let hub = ThermometerHub::with_config(
+        "127.0.0.1:0".to_string(),
+        HubConfig {
+            auto_register: AutoRegister::Only(HashSet::from([1])),
+            ..HubConfig::default()
+        },
+    )
+    .await?;
+    hub.register(3, "Hall")?;
+
+    for device_id in [1, 2, 3] {
+        let mut sender = Sender::new(&hub.local_addr().to_string(), device_id)
+            .await
+            .unwrap();
+        sender.send(20.0).await.unwrap();
+    }
+
+    let garbage = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    garbage.send_to(b"garbage", hub.local_addr()).await.unwrap();
+
+    wait_until(|| hub.stats().malformed == 1 && hub.sensors().len() == 2).await;
+    wait_until(|| hub.get_temperature("Hall").is_ok()).await;
+
+    assert!(hub.sensor_by_id(1).is_some());
+    assert!(hub.sensor_by_id(2).is_none());
+    assert_eq!(
+        hub.stats(),
+        HubStats {
+            malformed: 1,
+            rejected: 1,
+        }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_takes_temperature_of_sensor_datagrams() -> Result<(), ThermometerError> {
+    let hub = ThermometerHub::new("127.0.0.1:0".to_string()).await?;
+    hub.register(4, "Cellar")?;
+
+    let mut sender = Sender::new(&hub.local_addr().to_string(), 4).await.unwrap();
+    sender
+        .send_metrics(&[
+            (Metric::Temperature, 11.0),
+            (Metric::Humidity, 70.0),
+            (Metric::Co2, 500.0),
+            (Metric::Pressure, 1000.0),
+        ])
+        .await
+        .unwrap();
+
+    wait_until(|| hub.get_temperature("Cellar").is_ok()).await;
+
+    assert_eq!(hub.get_temperature("Cellar")?, 11.0);
+    assert_eq!(hub.stats(), HubStats::default());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unregister_forgets_sequence() -> Result<(), ThermometerError> {
+    let hub = ThermometerHub::new("127.0.0.1:0".to_string()).await?;
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    sender
+        .send_to(&Datagram::new(6, 5, 20.0).encode(), hub.local_addr())
+        .await
+        .unwrap();
+    wait_until(|| hub.get_temperature("thermometer-6").is_ok()).await;
+
+    hub.unregister("thermometer-6")?;
+
+    // Older than the forgotten reading, dropped as out of order otherwise
+    let restarted = Datagram {
+        timestamp: SystemTime::now() - Duration::from_secs(10),
+        ..Datagram::new(6, 1, 22.0)
+    };
+    sender
+        .send_to(&restarted.encode(), hub.local_addr())
+        .await
+        .unwrap();
+    wait_until(|| hub.get_temperature("thermometer-6").is_ok()).await;
+
+    assert_eq!(hub.get_temperature("thermometer-6")?, 22.0);
+    assert_eq!(hub.sensor("thermometer-6").unwrap().stats.out_of_order, 0);
+    assert!(hub.take_error().is_none());
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "net")]
+
+use std::time::Duration;
+
+use thermometer_udp::thermometer::{ReceiverStats, Thermometer};
+use tokio::net::UdpSocket;
+
+#[tokio::test]
+async fn test_shutdown_releases_socket() {
+    let thermo = Thermometer::new("Thermo#7".to_string(), "127.0.0.1:0".to_string())
+        .await
+        .unwrap();
+    let address = thermo.local_addr();
+
+    assert!(UdpSocket::bind(address).await.is_err());
+
+    thermo.shutdown().await;
+
+    let restarted = Thermometer::new("Thermo#7".to_string(), address.to_string()).await;
+
+    assert!(restarted.is_ok());
+}
+
+#[tokio::test]
+async fn test_drop_stops_receiver() {
+    let thermo = Thermometer::new("Thermo#8".to_string(), "127.0.0.1:0".to_string())
+        .await
+        .unwrap();
+    let address = thermo.local_addr();
+
+    drop(thermo);
+
+    // The aborted task lets go of the socket the next time the runtime polls it
+    let mut rebound = false;
+
+    for _ in 0..100 {
+        if UdpSocket::bind(address).await.is_ok() {
+            rebound = true;
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert!(rebound);
+}
+
+#[tokio::test]
+async fn test_counts_malformed_datagrams() {
+    let thermo = Thermometer::new("Thermo#9".to_string(), "127.0.0.1:0".to_string())
+        .await
+        .unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    for payload in [&b"garbage"[..], &[0; 4], &[]] {
+        sender.send_to(payload, thermo.local_addr()).await.unwrap();
+    }
+
+    for _ in 0..100 {
+        if thermo.stats().malformed == 3 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(
+        thermo.stats(),
+        ReceiverStats {
+            malformed: 3,
+            ..ReceiverStats::default()
+        }
+    );
+    assert!(thermo.take_error().is_none());
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "net")]
+
+use std::time::Duration;
+
+use thermometer_udp::sender::Sender;
+use thermometer_udp::thermometer::{Liveness, Thermometer, ThermometerConfig, ThermometerError};
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn test_goes_offline_and_back_online() {
+    let thermo = Thermometer::with_config(
+        "Thermo#6".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            jitter: 0.0,
+            stale_after: Duration::from_millis(100),
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut liveness = thermo.watch_liveness();
+
+    assert_eq!(thermo.liveness(), Liveness::Online);
+    assert_eq!(thermo.since_last_reading(), None);
+    assert_eq!(thermo.get_status(), "[Thermo#6] waiting for a reading");
+    assert!(matches!(
+        thermo.get_temperature(),
+        Err(ThermometerError::NoReading)
+    ));
+
+    timeout(Duration::from_secs(1), liveness.changed())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(*liveness.borrow(), Liveness::Offline);
+    assert_eq!(thermo.liveness(), Liveness::Offline);
+    assert_eq!(
+        thermo.get_status(),
+        "[Thermo#6] offline: no reading received"
+    );
+    assert!(matches!(
+        thermo.get_temperature(),
+        Err(ThermometerError::Stale {
+            since_last_reading: None
+        })
+    ));
+
+    let mut sender = Sender::new(&thermo.local_addr().to_string(), 1)
+        .await
+        .unwrap();
+    sender.send(21.0).await.unwrap();
+
+    timeout(Duration::from_secs(1), liveness.changed())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(*liveness.borrow(), Liveness::Online);
+    assert_eq!(thermo.get_temperature().unwrap(), 21.0);
+
+    timeout(Duration::from_secs(1), liveness.changed())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(*liveness.borrow(), Liveness::Offline);
+    assert!(thermo.since_last_reading().unwrap() >= Duration::from_millis(100));
+    assert!(thermo
+        .get_status()
+        .starts_with("[Thermo#6] stale: no reading for"));
+}
+
+#[tokio::test]
+async fn test_never_goes_stale_with_unbounded_timeout() {
+    let thermo = Thermometer::with_config(
+        "Thermo#10".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            jitter: 0.0,
+            stale_after: Duration::MAX,
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut sender = Sender::new(&thermo.local_addr().to_string(), 1)
+        .await
+        .unwrap();
+    sender.send(21.0).await.unwrap();
+
+    for _ in 0..100 {
+        if thermo.stats().accepted == 1 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(thermo.liveness(), Liveness::Online);
+    assert_eq!(thermo.get_temperature().unwrap(), 21.0);
+}
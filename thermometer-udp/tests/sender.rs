@@ -0,0 +1,130 @@
+#![cfg(feature = "net")]
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use thermometer_udp::sender::Sender;
+use thermometer_udp::thermometer::{ReceiverStats, Thermometer, ThermometerConfig};
+use thermometer_udp::waveform::{self, Constant, RandomWalk, Replay, Sine, Waveform, DAY};
+use tokio::net::UdpSocket;
+
+fn samples(waveform: &mut dyn Waveform, count: u32, interval: Duration) -> Vec<f32> {
+    (0..count)
+        .map_while(|i| waveform.sample(interval * i))
+        .collect()
+}
+
+#[test]
+fn test_constant_and_sine() {
+    let hour = Duration::from_secs(3600);
+
+    assert_eq!(samples(&mut Constant(20.0), 3, hour), vec![20.0; 3]);
+
+    let mut sine = Sine {
+        mean: 20.0,
+        amplitude: 5.0,
+        period: DAY,
+    };
+    let day = samples(&mut sine, 25, hour);
+
+    assert!((day[0] - 20.0).abs() < 1e-3);
+    assert!((day[6] - 25.0).abs() < 1e-3);
+    assert!((day[18] - 15.0).abs() < 1e-3);
+    assert!((day[24] - 20.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_random_walk_is_deterministic() {
+    let second = Duration::from_secs(1);
+    let first = samples(&mut RandomWalk::new(20.0, 0.5, 7), 50, second);
+    let second_run = samples(&mut RandomWalk::new(20.0, 0.5, 7), 50, second);
+
+    assert_eq!(first, second_run);
+    assert_eq!(first[0], 20.0);
+
+    let stuck = samples(&mut RandomWalk::new(20.0, f32::INFINITY, 7), 3, second);
+    assert_eq!(stuck, vec![20.0; 3]);
+    assert!(first.windows(2).all(|w| (w[1] - w[0]).abs() <= 0.5));
+}
+
+#[test]
+fn test_replay_from_csv() {
+    let csv = "time,temperature\n0,20.5\n60,21\n\n120,21.5\n";
+    let mut replay = Replay::from_csv(Cursor::new(csv)).unwrap();
+
+    assert_eq!(
+        samples(&mut replay, 10, Duration::from_secs(60)),
+        vec![20.5, 21.0, 21.5]
+    );
+}
+
+#[test]
+fn test_parse_waveform() {
+    let mut constant = waveform::parse_waveform("constant:18.5").unwrap();
+    assert_eq!(constant.sample(Duration::ZERO), Some(18.5));
+
+    assert!(waveform::parse_waveform("sine:20:5:60").is_ok());
+    assert!(waveform::parse_waveform("walk:20:0.5:42").is_ok());
+    assert!(waveform::parse_waveform("sine:20:5:0").is_err());
+    assert!(waveform::parse_waveform("sine:20:5:-1").is_err());
+    assert!(waveform::parse_waveform("sine:20:5:inf").is_err());
+    assert!(waveform::parse_waveform("sine:20:5:1e40").is_err());
+    assert!(waveform::parse_waveform("sine:20:5:1e-12").is_err());
+    assert!(waveform::parse_waveform("walk:20:NaN").is_err());
+    assert!(waveform::parse_waveform("constant:inf").is_err());
+    assert!(waveform::parse_waveform("square:1").is_err());
+}
+
+#[tokio::test]
+async fn test_sender_drives_thermometer() {
+    let thermo = Thermometer::with_config(
+        "Thermo#4".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            device_id: Some(3),
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut sender = Sender::new(&thermo.local_addr().to_string(), 3)
+        .await
+        .unwrap();
+    let mut replay = Replay::new(vec![19.0, 19.5, 20.0]);
+
+    let sent = sender
+        .run(&mut replay, Duration::from_millis(5), Some(10))
+        .await
+        .unwrap();
+
+    assert_eq!(sent, 3);
+
+    for _ in 0..100 {
+        if thermo.stats().accepted == 3 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(
+        thermo.stats(),
+        ReceiverStats {
+            accepted: 3,
+            ..ReceiverStats::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_local_addr_routes_to_target() {
+    let target = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let sender = Sender::new(&target.local_addr().unwrap().to_string(), 1)
+        .await
+        .unwrap();
+    let address = sender.local_addr().await.unwrap();
+
+    assert!(address.ip().is_loopback());
+    assert_ne!(address.port(), 0);
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "net")]
+
+use std::time::Duration;
+
+use thermometer_udp::hub::{HubConfig, ThermometerHub};
+use thermometer_udp::sender::Sender;
+use thermometer_udp::temperature::{Calibration, Temperature, TemperatureUnit};
+use thermometer_udp::thermometer::{Thermometer, ThermometerConfig};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-3,
+        "{} is not {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn test_conversions() {
+    let body = Temperature::from_celsius(37.0);
+
+    assert_close(body.fahrenheit(), 98.6);
+    assert_close(body.kelvin(), 310.15);
+    assert_close(Temperature::from_fahrenheit(32.0).celsius(), 0.0);
+    assert_close(Temperature::from_kelvin(0.0).celsius(), -273.15);
+    assert_close(
+        Temperature::new(212.0, TemperatureUnit::Fahrenheit).value_in(TemperatureUnit::Kelvin),
+        373.15,
+    );
+    assert!(Temperature::from_celsius(-1.0) < Temperature::from_fahrenheit(32.0));
+}
+
+#[test]
+fn test_display() {
+    let temperature = Temperature::from_celsius(26.0);
+
+    assert_eq!(temperature.to_string(), "26°C");
+    assert_eq!(
+        format!("{:.1}", temperature.display_in(TemperatureUnit::Fahrenheit)),
+        "78.8°F"
+    );
+    assert_eq!(
+        format!("{:.2}", temperature.display_in(TemperatureUnit::Kelvin)),
+        "299.15K"
+    );
+    assert_eq!("F".parse(), Ok(TemperatureUnit::Fahrenheit));
+    assert_eq!("kelvin".parse(), Ok(TemperatureUnit::Kelvin));
+    assert!("rankine".parse::<TemperatureUnit>().is_err());
+}
+
+#[test]
+fn test_calibration() {
+    let calibration = Calibration::new(-0.5, 1.1);
+
+    assert_close(
+        calibration.apply(Temperature::from_celsius(20.0)).celsius(),
+        21.5,
+    );
+    assert_eq!(
+        Calibration::default().apply(Temperature::from_celsius(20.0)),
+        Temperature::from_celsius(20.0)
+    );
+}
+
+#[tokio::test]
+async fn test_readings_are_calibrated_on_arrival() {
+    let thermo = Thermometer::with_config(
+        "Thermo#11".to_string(),
+        "127.0.0.1:0".to_string(),
+        ThermometerConfig {
+            jitter: 0.0,
+            calibration: Calibration::new(1.0, 2.0),
+            unit: TemperatureUnit::Fahrenheit,
+            ..ThermometerConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut sender = Sender::new(&thermo.local_addr().to_string(), 1)
+        .await
+        .unwrap();
+    sender.send(12.0).await.unwrap();
+
+    for _ in 0..100 {
+        if thermo.stats().accepted == 1 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(thermo.get_temperature().unwrap(), 25.0);
+    assert_eq!(thermo.history(Duration::from_secs(60))[0].temperature, 25.0);
+    assert_eq!(thermo.get_status(), "[Thermo#11] current temperature: 77°F");
+}
+
+#[tokio::test]
+async fn test_hub_calibrates_every_sensor() {
+    let hub = ThermometerHub::with_config(
+        "127.0.0.1:0".to_string(),
+        HubConfig {
+            unit: TemperatureUnit::Kelvin,
+            ..HubConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    hub.register(1, "Kitchen").unwrap();
+    hub.calibrate("Kitchen", Calibration::new(-2.0, 1.0))
+        .unwrap();
+
+    let mut sender = Sender::new(&hub.local_addr().to_string(), 1).await.unwrap();
+    sender.send(22.0).await.unwrap();
+
+    for _ in 0..100 {
+        if hub.get_temperature("Kitchen").is_ok() {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(
+        hub.temperature("Kitchen").unwrap(),
+        Temperature::from_celsius(20.0)
+    );
+    assert_eq!(
+        hub.get_status("Kitchen").unwrap(),
+        "[Kitchen] current temperature: 293.15K"
+    );
+}